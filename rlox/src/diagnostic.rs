@@ -0,0 +1,139 @@
+use std::io::IsTerminal;
+use crate::span::Span;
+use crate::LoxStaticError;
+use crate::loxStaticError::Severity;
+use crate::interpret::loxRuntimeError::{LoxRuntimeError, LoxRuntimeErrorType, LoxImportError};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorChoice
+{
+    Auto,
+    Always,
+    Never,
+}
+impl TryFrom<&str> for ColorChoice
+{
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, Self::Error>
+    {
+        match value {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            x => Err(format!("Invalid color choice {}, expected auto, always or never", x)),
+        }
+    }
+}
+
+//An error ready to be shown to the user, with any notes pointing at related code
+pub struct Diagnostic
+{
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    pub notes: Vec<(Span, String)>,
+    //Lines shown after the snippets, such as a stack trace
+    pub trace: Vec<String>,
+}
+impl From<&LoxStaticError> for Diagnostic
+{
+    fn from(error: &LoxStaticError) -> Self
+    {
+        let trace = match error.severity {
+            Severity::Error => Vec::new(),
+            Severity::Warning(lint) => vec![format!("= note: add \"// lint: allow({})\" to the line to allow this", lint)],
+        };
+        Diagnostic{ severity: error.severity, message: error.message.clone(), span: error.span, notes: error.notes.clone(), trace }
+    }
+}
+impl From<&LoxRuntimeError> for Diagnostic
+{
+    fn from(error: &LoxRuntimeError) -> Self
+    {
+        let trace = error.trace.iter().map(|f| format!("{}", f)).collect();
+        //The errors that stopped a module from running are shown where they are
+        let notes = match &error.errorType {
+            LoxRuntimeErrorType::ImportError(LoxImportError::Invalid(_, errors)) => errors.iter().map(|e| (e.span, e.message.clone())).collect(),
+            _ => Vec::new(),
+        };
+        Diagnostic{ severity: Severity::Error, message: format!("{}", error.errorType), span: error.span, notes, trace }
+    }
+}
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+//Renders diagnostics in the style of rustc, quoting the offending source line
+//and underlining the span
+pub struct Renderer
+{
+    color: bool,
+}
+impl Renderer
+{
+    //Auto only uses color when stderr, where diagnostics are written, is a terminal
+    pub fn new(choice: ColorChoice) -> Self
+    {
+        let color = match choice {
+            ColorChoice::Auto => std::io::stderr().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+        Renderer{ color }
+    }
+    fn paint(&self, color: &str, text: &str) -> String
+    {
+        match self.color {
+            true => format!("{}{}{}", color, text, RESET),
+            false => text.to_string(),
+        }
+    }
+    //sources holds the name and text of each file, indexed by Span::source
+    pub fn render(&self, diagnostic: &Diagnostic, sources: &[(String, String)]) -> String
+    {
+        //Every snippet shares a gutter wide enough for the largest line number
+        let width = diagnostic.notes.iter()
+            .map(|(s, _)| s.line)
+            .chain(std::iter::once(diagnostic.span.line))
+            .max().unwrap_or(0).to_string().len();
+        let (label, color) = match diagnostic.severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning(_) => ("warning", YELLOW),
+        };
+        let mut out = format!("{}{}\n", self.paint(color, label), self.paint(BOLD, format!(": {}", diagnostic.message).as_str()));
+        out += self.snippet(diagnostic.span, '^', color, sources, width).as_str();
+        for (span, note) in &diagnostic.notes {
+            out += format!("{}: {}\n", self.paint(BLUE, "note"), note).as_str();
+            out += self.snippet(*span, '-', BLUE, sources, width).as_str();
+        }
+        for line in &diagnostic.trace {
+            out += format!("{}\n", line).as_str();
+        }
+        out
+    }
+    fn snippet(&self, span: Span, marker: char, color: &str, sources: &[(String, String)], width: usize) -> String
+    {
+        let pad = " ".repeat(width);
+        //Code that was not read from a file, such as a tree loaded from JSON, has nothing to quote
+        let Some((file, source)) = sources.get(span.source as usize) else {
+            return format!("{}{} {}:{}\n", pad, self.paint(BLUE, "-->"), span.line, span.column);
+        };
+        let start = span.start.min(source.len());
+        let lineStart = source[..start].rfind('\n').map_or(0, |i| i+1);
+        let lineEnd = source[start..].find('\n').map_or(source.len(), |i| start+i);
+        let text = &source[lineStart..lineEnd];
+        let indent = source[lineStart..start].chars().count();
+        //Spans running over several lines are underlined up to the end of the first
+        let length = source[start..span.end().clamp(start, lineEnd)].chars().count().max(1);
+
+        let mut out = format!("{}{} {}:{}:{}\n", pad, self.paint(BLUE, "-->"), file, span.line, span.column);
+        out += format!("{} {}\n", pad, self.paint(BLUE, "|")).as_str();
+        out += format!("{} {} {}\n", self.paint(BLUE, format!("{:>width$}", span.line, width = width).as_str()), self.paint(BLUE, "|"), text).as_str();
+        let underline = marker.to_string().repeat(length);
+        out += format!("{} {} {}{}\n", pad, self.paint(BLUE, "|"), " ".repeat(indent), self.paint(color, underline.as_str())).as_str();
+        out
+    }
+}
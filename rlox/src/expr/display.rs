@@ -1,7 +1,11 @@
 use super::*;
-use super::stmt::{Stmt, Decl};
+use super::stmt::{Stmt, StmtType, Decl, DeclType, Function, Class};
 use std::fmt;
 use std::fmt::Display;
+fn parameters(params: &[Parameter]) -> String
+{
+    params.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", ")
+}
 impl Display for Boperator
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -55,11 +59,8 @@ impl Display for Literal
 impl Display for Call
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut arguments = String::new();
-        for a in &self.arguments {
-            arguments.push_str(format!("{}", a).as_str());
-        }
-        write!(f, "{}({})", self.callee, arguments)
+        let arguments: Vec<String> = self.arguments.iter().map(|a| format!("{}", a)).collect();
+        write!(f, "{}({})", self.callee, arguments.join(", "))
     }
 }
 impl Display for Unary
@@ -92,18 +93,86 @@ impl Display for Assignment
         write!(f, "{} = {}", self.id, self.expr)
     }
 }
+impl Display for Get
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.object, self.name)
+    }
+}
+impl Display for Set
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{} = {}", self.object, self.name, self.value)
+    }
+}
+impl Display for List
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elements: Vec<String> = self.elements.iter().map(|e| format!("{}", e)).collect();
+        write!(f, "[{}]", elements.join(", "))
+    }
+}
+impl Display for Lambda
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut body = String::new();
+        for d in &self.body {
+            body.push_str(format!("{}\n", d).as_str());
+        }
+        write!(f, "fun ({}) {{\n{}}}", parameters(&self.params), body)
+    }
+}
+impl Display for Interpolation
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.parts.iter().map(|p| match p {
+            Part::Text(s) => s.clone(),
+            Part::Value(v) => format!("${{{}}}", v),
+        }).collect();
+        write!(f, "\"{}\"", parts.concat())
+    }
+}
+impl Display for Map
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self.entries.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+        write!(f, "{{{}}}", entries.join(", "))
+    }
+}
+impl Display for Index
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.object, self.index)
+    }
+}
+impl Display for IndexSet
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}] = {}", self.object, self.index, self.value)
+    }
+}
 impl Display for Expr
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let repr = match self.exprType {
             ExprType::Literal(ref l) => format!("{}", l),
-            ExprType::Variable(ref n) => n.clone(),
+            ExprType::Variable(ref v) => v.name.clone(),
             ExprType::Assignment(ref a) => format!("{}", a),
             ExprType::Call(ref c) => format!("{}", c),
             ExprType::Unary(ref u) => format!("({})", u),
             ExprType::Logical(ref l) => format!("({})", l),
             ExprType::Binary(ref b) => format!("({})", b),
             ExprType::Grouping(ref g) => format!("({})", g),
+            ExprType::Get(ref g) => format!("{}", g),
+            ExprType::Set(ref s) => format!("{}", s),
+            ExprType::This(_) => "this".to_string(),
+            ExprType::Super(ref s) => format!("super.{}", s.method),
+            ExprType::List(ref l) => format!("{}", l),
+            ExprType::Map(ref m) => format!("{}", m),
+            ExprType::Index(ref i) => format!("{}", i),
+            ExprType::IndexSet(ref i) => format!("{}", i),
+            ExprType::Lambda(ref l) => format!("{}", l),
+            ExprType::Interpolation(ref i) => format!("{}", i),
         };
         write!(f, "{}", repr)
     }
@@ -111,19 +180,46 @@ impl Display for Expr
 impl Display for Stmt
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let repr = match self {
-            Self::PrintStmt(e) => format!("print {};", e),
-            Self::IfStmt(c, i, e) => {
+        let repr = match &self.stmtType {
+            StmtType::PrintStmt(e) => format!("print {};", e),
+            StmtType::IfStmt(c, i, e) => {
                 let elseStr = match e {
                     Some(x) => format!("\nelse {}", x),
                     None => String::new(),
                 };
                 format!("if {}\n{}{}", c, i, elseStr)
             },
-            Self::WhileStmt(c, b) => format!("while {}\n{};", c, b),
-            Self::ExprStmt(e) => format!("{};", e),
-            Self::ReturnStmt(r) => format!("return {};", r),
-            Self::Block(b) => {
+            StmtType::WhileStmt(c, b) => format!("while {}\n{};", c, b),
+            StmtType::ForStmt(l) => {
+                let clause = |e: &Option<Expr>| e.as_ref().map_or(String::new(), |e| format!("{}", e));
+                let initialiser = l.initialiser.as_ref().map_or(String::new(), |i| format!("{}", i));
+                format!("for {}\n{}\n{}\n{};", initialiser, clause(&l.condition), clause(&l.increment), l.body)
+            },
+            StmtType::ExprStmt(e) => format!("{};", e),
+            StmtType::ReturnStmt(Some(r)) => format!("return {};", r),
+            StmtType::ReturnStmt(None) => "return;".to_string(),
+            StmtType::BreakStmt => "break;".to_string(),
+            StmtType::ContinueStmt => "continue;".to_string(),
+            StmtType::ThrowStmt(e) => format!("throw {};", e),
+            StmtType::TryStmt(b, c, f) => {
+                let block = |decls: &Vec<Decl>| {
+                    let mut repr = "{\n".to_string();
+                    for d in decls.iter() {
+                        repr.push_str(format!("{}\n", d).as_str());
+                    }
+                    repr.push('}');
+                    repr
+                };
+                let mut repr = format!("try {}", block(b));
+                if let Some((name, c)) = c {
+                    repr.push_str(format!("\ncatch ({}) {}", name.name, block(c)).as_str());
+                }
+                if let Some(f) = f {
+                    repr.push_str(format!("\nfinally {}", block(f)).as_str());
+                }
+                repr
+            },
+            StmtType::Block(b) => {
                 let mut repr = "{\n".to_string();
                 for d in b.iter() {
                     repr.push_str(format!("{}\n", d).as_str());
@@ -135,26 +231,42 @@ impl Display for Stmt
         write!(f, "{}", repr)
     }
 }
+impl Display for Function
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params = format!("({}) {{\n", parameters(&self.params));
+        let mut body = String::new();
+        for d in &self.body {
+            body.push_str(format!("{}\n", d).as_str());
+        }
+        body.push('}');
+        write!(f, "{}{}{}", self.name, params, body)
+    }
+}
+impl Display for Class
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut repr = match &self.superclass {
+            Some(s) => format!("class {} < {} {{\n", self.name, s),
+            None => format!("class {} {{\n", self.name),
+        };
+        for m in &self.methods {
+            repr.push_str(format!("{}\n", m).as_str());
+        }
+        repr.push('}');
+        write!(f, "{}", repr)
+    }
+}
 impl Display for Decl
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let repr = match self {
-            Self::FunDecl(n, p, b) => {
-                let first = format!("fun {}", n);
-                let mut params = "(".to_string();
-                for a in p {
-                    params.push_str(format!("{}", a).as_str());
-                }
-                params.push_str(") {\n");
-                let mut body = String::new();
-                for d in b {
-                    body.push_str(format!("{}\n", d).as_str());
-                }
-                body.push_str("}");
-                format!("{}{}{}", first, params, body)
-            },
-            Self::VarDecl(n, e) => format!("var {} = {};", n, e),
-            Self::Stmt(s) => format!("{}", s),
+        let repr = match &self.declType {
+            DeclType::FunDecl(func) => format!("fun {}", func),
+            DeclType::ClassDecl(c) => format!("{}", c),
+            DeclType::VarDecl(n, Some(e)) => format!("var {} = {};", n, e),
+            DeclType::VarDecl(n, None) => format!("var {};", n),
+            DeclType::Import(p, n) => format!(r#"import "{}" as {};"#, p, n),
+            DeclType::Stmt(s) => format!("{}", s),
         };
         write!(f, "{}", repr)
     }
@@ -2,39 +2,137 @@ pub mod display;
 pub mod fromToken;
 pub mod stmt;
 
-#[derive(Clone)]
+use serde::{Serialize, Deserialize};
+use crate::span::Span;
+use stmt::Decl;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Expr
 {
     pub exprType: ExprType,
-    pub line: u32,
+    pub span: Span,
 }
 impl Expr
 {
-    pub fn new(exprType: ExprType, line: u32) -> Self
+    pub fn new(exprType: ExprType, span: Span) -> Self
     {
-        Expr{exprType, line}
+        Expr{exprType, span}
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum ExprType
 {
     Literal(Literal),
-    Variable(String),
+    Variable(Variable),
     Assignment(Assignment),
     Call(Call),
     Unary(Unary),
     Logical(Logical),
     Binary(Binary),
     Grouping(Grouping),
+    Get(Get),
+    Set(Set),
+    This(#[serde(default)] Option<usize>),
+    Super(Super),
+    List(List),
+    Map(Map),
+    Index(Index),
+    IndexSet(IndexSet),
+    Lambda(Lambda),
+    Interpolation(Interpolation),
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Call
 {
     pub callee: Box<Expr>,
     pub arguments: Vec<Expr>,
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Get
+{
+    pub object: Box<Expr>,
+    pub name: String,
+}
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Set
+{
+    pub object: Box<Expr>,
+    pub name: String,
+    pub value: Box<Expr>,
+}
+#[derive(Clone, Serialize, Deserialize)]
+pub struct List
+{
+    pub elements: Vec<Expr>,
+}
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Map
+{
+    pub entries: Vec<(Expr, Expr)>,
+}
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Index
+{
+    pub object: Box<Expr>,
+    pub index: Box<Expr>,
+}
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IndexSet
+{
+    pub object: Box<Expr>,
+    pub index: Box<Expr>,
+    pub value: Box<Expr>,
+}
+//An anonymous function. The arrow form's expression becomes a single return statement
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Lambda
+{
+    pub params: Vec<Parameter>,
+    pub body: Vec<Decl>,
+    //Whether it was written in the arrow form
+    #[serde(default)]
+    pub arrow: bool,
+}
+//A string with expressions in it, such as "a${b}c"
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Interpolation
+{
+    pub parts: Vec<Part>,
+}
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Part
+{
+    Text(String),
+    //Converted to a string the way "str" would
+    Value(Expr),
+}
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Super
+{
+    pub method: String,
+    #[serde(default)]
+    pub depth: Option<usize>,
+}
+//A parameter of a function or lambda, with the span of its name
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Parameter
+{
+    pub name: String,
+    #[serde(default)]
+    pub span: Span,
+}
+//depth is the number of scopes between the use and the declaration, filled in by the resolver.
+//None means the variable is global
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Variable
+{
+    pub name: String,
+    #[serde(default)]
+    pub depth: Option<usize>,
+}
+//Compared the way the interpreter compares values, so NaN is not equal to itself
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum Literal
 {
     Num(f64),
@@ -42,37 +140,39 @@ pub enum Literal
     Bool(bool),
     Nil,
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Assignment
 {
     pub id: String,
     pub expr: Box<Expr>,
+    #[serde(default)]
+    pub depth: Option<usize>,
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Grouping
 {
     pub expr: Box<Expr>
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Unary
 {
     pub operator: Uoperator,
     pub expr: Box<Expr>,
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Uoperator
 {
     Minus,
     Bang,
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Binary
 {
     pub lexpr: Box<Expr>,
     pub operator: Boperator,
     pub rexpr: Box<Expr>,
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Boperator
 {
     EqualEqual,
@@ -86,14 +186,14 @@ pub enum Boperator
     Star,
     Slash,
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Logical
 {
     pub lexpr: Box<Expr>,
     pub operator: Loperator,
     pub rexpr: Box<Expr>,
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Loperator
 {
     Or,
@@ -1,20 +1,91 @@
-use super::Expr;
+use serde::{Serialize, Deserialize};
+use super::{Expr, Parameter};
+use crate::span::Span;
 
-#[derive(Clone)]
-pub enum Decl
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Decl
 {
-    FunDecl(String, Vec<String>, Vec<Decl>),
-    VarDecl(String, Expr),
+    pub declType: DeclType,
+    pub span: Span,
+}
+impl Decl
+{
+    pub fn new(declType: DeclType, span: Span) -> Self
+    {
+        Decl{declType, span}
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum DeclType
+{
+    FunDecl(Function),
+    ClassDecl(Class),
+    //The value is None for "var x;", which declares it as nil
+    VarDecl(String, Option<Expr>),
+    //The path as written and the name the module is bound to
+    Import(String, String),
     Stmt(Stmt),
 }
 
-#[derive(Clone)]
-pub enum Stmt
+//span is the span of the name, while the enclosing Decl covers the whole declaration
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Function
+{
+    pub name: String,
+    pub params: Vec<Parameter>,
+    pub body: Vec<Decl>,
+    pub span: Span,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Class
+{
+    pub name: String,
+    pub superclass: Option<Expr>,
+    pub methods: Vec<Function>,
+    pub span: Span,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Stmt
+{
+    pub stmtType: StmtType,
+    pub span: Span,
+}
+impl Stmt
+{
+    pub fn new(stmtType: StmtType, span: Span) -> Self
+    {
+        Stmt{stmtType, span}
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum StmtType
 {
     ExprStmt(Expr),
     IfStmt(Expr, Box<Stmt>, Option<Box<Stmt>>),
     WhileStmt(Expr, Box<Stmt>),
+    ForStmt(For),
     PrintStmt(Expr),
-    ReturnStmt(Expr),
+    //None for "return;", which returns nil
+    ReturnStmt(Option<Expr>),
+    BreakStmt,
+    ContinueStmt,
+    ThrowStmt(Expr),
+    //The body, then the optional catch clause with the name it binds, then the optional finally block
+    TryStmt(Vec<Decl>, Option<(Parameter, Vec<Decl>)>, Option<Vec<Decl>>),
     Block(Vec<Decl>),
 }
+
+//A "for" loop, which may leave out any of its clauses. A missing condition is always true, and
+//the initialiser is scoped to the loop. The increment runs after each pass, even after "continue"
+#[derive(Clone, Serialize, Deserialize)]
+pub struct For
+{
+    pub initialiser: Option<Box<Decl>>,
+    pub condition: Option<Expr>,
+    pub increment: Option<Expr>,
+    pub body: Box<Stmt>,
+}
@@ -0,0 +1,504 @@
+use std::path::Path;
+use crate::scan::scanTrivia;
+use crate::parse::Parser;
+use crate::span::Span;
+use crate::token::{Token, TokenType, Comment};
+use crate::expr::*;
+use crate::expr::stmt::{Stmt, StmtType, Decl, DeclType, Function, Class, For};
+use crate::LoxStaticError;
+
+//How formatted code is laid out
+#[derive(Clone, Copy)]
+pub struct Style
+{
+    //Spaces per level of nesting
+    pub indent: usize,
+    //Calls, lists and maps that would run past this column are split over several lines
+    pub width: usize,
+}
+impl Default for Style
+{
+    fn default() -> Self
+    {
+        Style{ indent: 2, width: 80 }
+    }
+}
+
+//Lays source out in the canonical style, keeping its comments. Fails if it does not parse
+pub fn format(source: &str, style: Style) -> Result<String, Vec<LoxStaticError>>
+{
+    let (tokens, comments, errors) = scanTrivia(source.to_string(), 0);
+    if !errors.is_empty() { return Err(errors); }
+    let (program, errors) = Parser::new(tokens.clone()).parse();
+    if !errors.is_empty() { return Err(errors); }
+    let mut printer = Printer{ source, style, tokens, comments, next: 0, cursor: 0, level: 0 };
+    Ok(printer.decls(&program, source.len()))
+}
+
+//The column reached after writing text starting at column
+fn after(column: usize, text: &str) -> usize
+{
+    match text.rfind('\n') {
+        Some(i) => text[i+1..].chars().count(),
+        None => column + text.chars().count(),
+    }
+}
+
+fn parameters(params: &[Parameter]) -> String
+{
+    params.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+//Writes a string as a literal that scans back to it
+fn quote(string: &str) -> String
+{
+    format!("\"{}\"", escape(string))
+}
+
+//Escapes what cannot appear as itself between the quotes of a string literal
+fn escape(string: &str) -> String
+{
+    let mut out = String::new();
+    let mut chars = string.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\t' => out += "\\t",
+            '\r' => out += "\\r",
+            '\0' => out += "\\0",
+            '$' if chars.peek() == Some(&'{') => out += "\\$",
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+//Each comment belongs to the token after it, and is printed just before the node or keyword
+//that token starts. Comments are kept in source order, so that is wherever the printer has
+//reached the token's offset
+struct Printer<'a>
+{
+    source: &'a str,
+    style: Style,
+    //Used to find keywords and brackets, which the tree has no spans for
+    tokens: Vec<Token>,
+    comments: Vec<Comment>,
+    //The first comment not printed yet
+    next: usize,
+    //The end of the last thing printed, used to keep blank lines between declarations
+    cursor: usize,
+    level: usize,
+}
+impl<'a> Printer<'a>
+{
+    fn text(&self, span: Span) -> &'a str
+    {
+        &self.source[span.start..span.end()]
+    }
+    fn indent(&self) -> String
+    {
+        " ".repeat(self.level * self.style.indent)
+    }
+    //The column an expression starts at when it follows prefix at the start of a line
+    fn column(&self, prefix: &str) -> usize
+    {
+        self.level * self.style.indent + prefix.chars().count()
+    }
+    fn moveTo(&mut self, offset: usize)
+    {
+        self.cursor = self.cursor.max(offset);
+    }
+    //Whether a blank line separates the last thing printed from the code at offset
+    fn blankBefore(&self, offset: usize) -> bool
+    {
+        offset > self.cursor && self.source[self.cursor..offset].matches('\n').count() > 1
+    }
+    //Prints the comments before offset on lines of their own
+    fn comments(&mut self, offset: usize, first: &mut bool) -> String
+    {
+        let mut out = String::new();
+        while let Some(comment) = self.comments.get(self.next).filter(|c| c.span.start < offset).cloned() {
+            if !*first && self.blankBefore(comment.span.start) {
+                out.push('\n');
+            }
+            out += format!("{}{}\n", self.indent(), comment.text).as_str();
+            self.next += 1;
+            self.moveTo(comment.span.end());
+            *first = false;
+        }
+        out
+    }
+    //Prints the comment after offset if it is on the same line, and before limit,
+    //where the next thing to be printed starts
+    fn trailing(&mut self, offset: usize, limit: usize) -> String
+    {
+        match self.comments.get(self.next).cloned() {
+            Some(c) if c.span.start >= offset && c.span.start < limit && !self.source[offset..c.span.start].contains('\n') => {
+                self.next += 1;
+                self.moveTo(c.span.end());
+                format!(" {}", c.text)
+            },
+            _ => String::new(),
+        }
+    }
+    //Prints the comments before offset, the token they are attached to. Each goes after the code
+    //it shares a line with, or else on a line of its own
+    fn attached(&mut self, offset: usize) -> String
+    {
+        let mut out = String::new();
+        while let Some(comment) = self.comments.get(self.next).filter(|c| c.span.start < offset).cloned() {
+            let before = self.tokens.partition_point(|t| t.span.end() <= comment.span.start);
+            match before > 0 && !self.source[self.tokens[before-1].span.end()..comment.span.start].contains('\n') {
+                true => out += format!(" {}", comment.text).as_str(),
+                false => out += format!("\n{}{}", self.indent(), comment.text).as_str(),
+            }
+            self.next += 1;
+            self.moveTo(comment.span.end());
+        }
+        out
+    }
+    //Separates what was printed last from the code at offset. The comments before it follow
+    //on the same line if they were written there, or else on lines of their own, and the code
+    //goes on the next line. Without comments, separator is used
+    fn gap(&mut self, offset: usize, separator: &str) -> String
+    {
+        match self.attached(offset) {
+            out if out.is_empty() => separator.to_string(),
+            out => format!("{}\n{}", out, self.indent()),
+        }
+    }
+    //The offset of the first token of the type at or after from
+    fn find(&self, tokenType: TokenType, from: usize) -> usize
+    {
+        self.tokens.iter().find(|t| t.span.start >= from && t.tokenType == tokenType).map_or(from, |t| t.span.start)
+    }
+    //The closing brace of a block whose opening brace is the first one after open
+    fn blockEnd(&self, decls: &[Decl], open: usize) -> usize
+    {
+        match decls.last() {
+            Some(d) => self.find(TokenType::RIGHT_BRACE, d.span.end()),
+            None => self.find(TokenType::RIGHT_BRACE, self.find(TokenType::LEFT_BRACE, open)),
+        }
+    }
+
+    //Prints each declaration on its own lines, then any comments before end
+    fn decls(&mut self, decls: &[Decl], end: usize) -> String
+    {
+        let mut out = String::new();
+        let mut first = true;
+        for (i, decl) in decls.iter().enumerate() {
+            out += self.comments(decl.span.start, &mut first).as_str();
+            if !first && self.blankBefore(decl.span.start) {
+                out.push('\n');
+            }
+            let text = self.decl(decl);
+            let comment = self.trailing(decl.span.end(), decls.get(i+1).map_or(end, |d| d.span.start));
+            out += format!("{}{}{}\n", self.indent(), text, comment).as_str();
+            self.moveTo(decl.span.end());
+            first = false;
+        }
+        out += self.comments(end, &mut first).as_str();
+        out
+    }
+    //A block after a header such as "try", with the comments between them. The opening
+    //brace is the first one after from, and end is the offset of the closing brace
+    fn braced(&mut self, decls: &[Decl], from: usize, end: usize) -> String
+    {
+        let separator = self.gap(self.find(TokenType::LEFT_BRACE, from), " ");
+        separator + self.block(decls, end).as_str()
+    }
+    //end is the offset of the closing brace
+    fn block(&mut self, decls: &[Decl], end: usize) -> String
+    {
+        self.level += 1;
+        let inner = self.decls(decls, end);
+        self.level -= 1;
+        self.moveTo(end + 1);
+        match inner.is_empty() {
+            true => "{}".to_string(),
+            false => format!("{{\n{}{}}}", inner, self.indent()),
+        }
+    }
+    fn decl(&mut self, decl: &Decl) -> String
+    {
+        match &decl.declType {
+            DeclType::FunDecl(f) => format!("fun {}", self.function(f, decl.span.end() - 1)),
+            DeclType::ClassDecl(c) => self.class(c, decl.span.end() - 1),
+            DeclType::VarDecl(name, value) => self.varDecl(name, value),
+            DeclType::Import(path, name) => {
+                //"as" is left out when the module is named after its file anyway
+                let stem = Path::new(path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                match stem == *name {
+                    true => format!("import {};", quote(path)),
+                    false => format!("import {} as {};", quote(path), name),
+                }
+            },
+            DeclType::Stmt(s) => self.stmt(s),
+        }
+    }
+    fn varDecl(&mut self, name: &str, value: &Option<Expr>) -> String
+    {
+        let Some(value) = value else { return format!("var {};", name) };
+        let prefix = format!("var {} = ", name);
+        let value = self.expr(value, self.column(&prefix));
+        format!("{}{};", prefix, value)
+    }
+    fn function(&mut self, function: &Function, end: usize) -> String
+    {
+        let body = self.braced(&function.body, function.span.end(), end);
+        format!("{}({}){}", function.name, parameters(&function.params), body)
+    }
+    fn class(&mut self, class: &Class, end: usize) -> String
+    {
+        let header = match &class.superclass {
+            Some(s) => format!("class {} < {}", class.name, self.expr(s, 0)),
+            None => format!("class {}", class.name),
+        };
+        self.level += 1;
+        let mut out = String::new();
+        let mut first = true;
+        for (i, method) in class.methods.iter().enumerate() {
+            out += self.comments(method.span.start, &mut first).as_str();
+            if !first && self.blankBefore(method.span.start) {
+                out.push('\n');
+            }
+            let methodEnd = self.blockEnd(&method.body, method.span.end());
+            let text = self.function(method, methodEnd);
+            let comment = self.trailing(methodEnd + 1, class.methods.get(i+1).map_or(end, |m| m.span.start));
+            out += format!("{}{}{}\n", self.indent(), text, comment).as_str();
+            first = false;
+        }
+        out += self.comments(end, &mut first).as_str();
+        self.level -= 1;
+        self.moveTo(end + 1);
+        match out.is_empty() {
+            true => format!("{} {{}}", header),
+            false => format!("{} {{\n{}{}}}", header, out, self.indent()),
+        }
+    }
+    fn stmt(&mut self, stmt: &Stmt) -> String
+    {
+        match &stmt.stmtType {
+            StmtType::ExprStmt(e) => format!("{};", self.expr(e, self.column(""))),
+            StmtType::PrintStmt(e) => format!("print {};", self.expr(e, self.column("print "))),
+            StmtType::ReturnStmt(None) => "return;".to_string(),
+            StmtType::ReturnStmt(Some(e)) => format!("return {};", self.expr(e, self.column("return "))),
+            StmtType::ThrowStmt(e) => format!("throw {};", self.expr(e, self.column("throw "))),
+            StmtType::BreakStmt => "break;".to_string(),
+            StmtType::ContinueStmt => "continue;".to_string(),
+            StmtType::IfStmt(condition, then, otherwise) => {
+                let condition = self.expr(condition, self.column("if ("));
+                let mut out = format!("if ({}){}", condition, self.body(then));
+                if let Some(otherwise) = otherwise {
+                    let separator = match then.stmtType {
+                        StmtType::Block(_) => " ".to_string(),
+                        _ => format!("\n{}", self.indent()),
+                    };
+                    let separator = self.gap(self.find(TokenType::ELSE, then.span.end()), &separator);
+                    out += format!("{}else{}", separator, self.body(otherwise)).as_str();
+                }
+                out
+            },
+            StmtType::ForStmt(l) => self.forLoop(l),
+            StmtType::WhileStmt(condition, body) => {
+                let condition = self.expr(condition, self.column("while ("));
+                format!("while ({}){}", condition, self.body(body))
+            },
+            StmtType::TryStmt(body, catch, finally) => {
+                let mut end = self.blockEnd(body, stmt.span.start);
+                let mut out = format!("try{}", self.braced(body, stmt.span.start, end));
+                if let Some((name, decls)) = catch {
+                    let keyword = self.find(TokenType::CATCH, end);
+                    let separator = self.gap(keyword, " ");
+                    end = self.blockEnd(decls, keyword);
+                    out += format!("{}catch ({}){}", separator, name.name, self.braced(decls, keyword, end)).as_str();
+                }
+                if let Some(decls) = finally {
+                    let keyword = self.find(TokenType::FINALLY, end);
+                    let separator = self.gap(keyword, " ");
+                    end = self.blockEnd(decls, keyword);
+                    out += format!("{}finally{}", separator, self.braced(decls, keyword, end)).as_str();
+                }
+                out
+            },
+            StmtType::Block(decls) => self.block(decls, stmt.span.end() - 1),
+        }
+    }
+    //A statement after a header such as "while (x)", with the comments between them
+    fn body(&mut self, stmt: &Stmt) -> String
+    {
+        let separator = self.gap(stmt.span.start, " ");
+        separator + self.stmt(stmt).as_str()
+    }
+    fn forLoop(&mut self, l: &For) -> String
+    {
+        let column = self.column("for (");
+        let initialiser = match l.initialiser.as_ref().map(|d| &d.declType) {
+            Some(DeclType::VarDecl(name, value)) => self.varDecl(name, value),
+            Some(DeclType::Stmt(s)) => self.stmt(s),
+            _ => ";".to_string(),
+        };
+        let condition = match &l.condition {
+            Some(c) => format!(" {}", self.expr(c, column)),
+            None => String::new(),
+        };
+        let increment = match &l.increment {
+            Some(i) => format!(" {}", self.expr(i, column)),
+            None => String::new(),
+        };
+        format!("for ({}{};{}){}", initialiser, condition, increment, self.body(&l.body))
+    }
+
+    //column is where the expression starts on its line, used to decide whether to split it.
+    //Comments before an operand inside the expression end the line, and the operand starts the next
+    fn expr(&mut self, expr: &Expr, column: usize) -> String
+    {
+        self.level += 1;
+        let lead = self.gap(expr.span.start, "").trim_start().to_string();
+        self.level -= 1;
+        let column = match lead.is_empty() {
+            true => column,
+            false => after(column, &lead),
+        };
+        lead + self.node(expr, column).as_str()
+    }
+    fn node(&mut self, expr: &Expr, column: usize) -> String
+    {
+        match &expr.exprType {
+            ExprType::Literal(_) => self.text(expr.span).to_string(),
+            ExprType::Variable(v) => v.name.clone(),
+            ExprType::This(_) => "this".to_string(),
+            ExprType::Super(s) => format!("super.{}", s.method),
+            ExprType::Assignment(a) => {
+                let prefix = format!("{} = ", a.id);
+                let value = self.expr(&a.expr, after(column, &prefix));
+                prefix + value.as_str()
+            },
+            ExprType::Get(g) => {
+                let object = self.expr(&g.object, column);
+                format!("{}.{}", object, g.name)
+            },
+            ExprType::Set(s) => {
+                let object = self.expr(&s.object, column);
+                let prefix = format!("{}.{} = ", object, s.name);
+                let value = self.expr(&s.value, after(column, &prefix));
+                prefix + value.as_str()
+            },
+            ExprType::Index(i) => {
+                let object = self.expr(&i.object, column);
+                let index = self.expr(&i.index, after(column, &object) + 1);
+                format!("{}[{}]", object, index)
+            },
+            ExprType::IndexSet(i) => {
+                let object = self.expr(&i.object, column);
+                let index = self.expr(&i.index, after(column, &object) + 1);
+                let prefix = format!("{}[{}] = ", object, index);
+                let value = self.expr(&i.value, after(column, &prefix));
+                prefix + value.as_str()
+            },
+            ExprType::Unary(u) => {
+                let operator = u.operator.to_string();
+                let operand = self.expr(&u.expr, column + operator.len());
+                operator + operand.as_str()
+            },
+            ExprType::Binary(b) => self.infix(&b.lexpr, &b.operator.to_string(), &b.rexpr, column),
+            ExprType::Logical(l) => self.infix(&l.lexpr, &l.operator.to_string(), &l.rexpr, column),
+            ExprType::Grouping(g) => format!("({})", self.expr(&g.expr, column + 1)),
+            ExprType::Call(c) => {
+                let callee = self.expr(&c.callee, column);
+                let arguments = self.list(("(", ")"), &c.arguments, expr.span, after(column, &callee), Printer::expr, |a| a.span);
+                callee + arguments.as_str()
+            },
+            ExprType::List(l) => self.list(("[", "]"), &l.elements, expr.span, column, Printer::expr, |e| e.span),
+            ExprType::Map(m) => self.list(("{", "}"), &m.entries, expr.span, column, Printer::entry, |(k, v)| k.span.to(v.span)),
+            ExprType::Lambda(l) => self.lambda(l, expr.span, column),
+            ExprType::Interpolation(i) => self.interpolation(i, column),
+        }
+    }
+    fn infix(&mut self, left: &Expr, operator: &str, right: &Expr, column: usize) -> String
+    {
+        let left = self.expr(left, column);
+        let prefix = format!("{} {} ", left, operator);
+        let right = self.expr(right, after(column, &prefix));
+        prefix + right.as_str()
+    }
+    fn entry(&mut self, (key, value): &(Expr, Expr), column: usize) -> String
+    {
+        let key = self.expr(key, column);
+        let prefix = format!("{}: ", key);
+        let value = self.expr(value, after(column, &prefix));
+        prefix + value.as_str()
+    }
+    //Puts the items on one line if they fit, or else one to a line. span covers the whole
+    //list, ending with its closing bracket. Comments between the items also put them one to a line
+    fn list<T>(&mut self, (open, close): (&str, &str), items: &[T], span: Span, column: usize, item: fn(&mut Self, &T, usize) -> String, itemSpan: fn(&T) -> Span) -> String
+    {
+        let end = span.end() - 1;
+        let within = |offset: usize, s: Span| s.start <= offset && offset < s.end();
+        let between = self.comments[self.next..].iter()
+            .take_while(|c| c.span.start < end)
+            .any(|c| !items.iter().any(|x| within(c.span.start, itemSpan(x))));
+        if !between {
+            //Comments are printed by function bodies, so a rejected attempt must put them back
+            let saved = (self.next, self.cursor);
+            let mut flat = open.to_string();
+            for (i, x) in items.iter().enumerate() {
+                if i > 0 {
+                    flat += ", ";
+                }
+                let text = item(self, x, after(column, &flat));
+                flat += text.as_str();
+            }
+            flat += close;
+            let firstLine = flat.lines().next().unwrap_or("");
+            if items.is_empty() || column + firstLine.chars().count() <= self.style.width {
+                return flat;
+            }
+            (self.next, self.cursor) = saved;
+        }
+        self.level += 1;
+        let indent = self.indent();
+        let mut out = open.to_string();
+        for (i, x) in items.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out += self.attached(itemSpan(x).start).as_str();
+            let text = item(self, x, indent.len());
+            out += format!("\n{}{}", indent, text).as_str();
+        }
+        out += self.attached(end).as_str();
+        self.level -= 1;
+        format!("{}\n{}{}", out, self.indent(), close)
+    }
+    fn lambda(&mut self, lambda: &Lambda, span: Span, column: usize) -> String
+    {
+        //The arrow form is parsed as a body returning its expression
+        if lambda.arrow {
+            if let [Decl{ declType: DeclType::Stmt(Stmt{ stmtType: StmtType::ReturnStmt(Some(value)), .. }), .. }] = &lambda.body[..] {
+                let prefix = format!("({}) => ", parameters(&lambda.params));
+                let value = self.expr(value, after(column, &prefix));
+                return prefix + value.as_str();
+            }
+        }
+        let body = self.braced(&lambda.body, span.start, span.end() - 1);
+        format!("fun ({}){}", parameters(&lambda.params), body)
+    }
+    fn interpolation(&mut self, interpolation: &Interpolation, column: usize) -> String
+    {
+        let mut out = String::from('"');
+        for part in &interpolation.parts {
+            match part {
+                Part::Text(text) => out += escape(text).as_str(),
+                Part::Value(value) => {
+                    let value = self.expr(value, after(column, &out) + 2);
+                    out += format!("${{{}}}", value).as_str();
+                },
+            }
+        }
+        out.push('"');
+        out
+    }
+}
@@ -0,0 +1,111 @@
+//Conversions between Lox values and Rust types, for programs embedding the interpreter
+use super::loxValue::{LoxValue, LoxList};
+use super::loxType::LoxType;
+use super::loxRuntimeError::loxTypeError::LoxTypeMismatch;
+
+impl From<f64> for LoxValue
+{
+    fn from(n: f64) -> Self
+    {
+        LoxValue::Num(n)
+    }
+}
+impl From<i32> for LoxValue
+{
+    fn from(n: i32) -> Self
+    {
+        LoxValue::Num(n as f64)
+    }
+}
+impl From<bool> for LoxValue
+{
+    fn from(b: bool) -> Self
+    {
+        LoxValue::Bool(b)
+    }
+}
+impl From<String> for LoxValue
+{
+    fn from(s: String) -> Self
+    {
+        LoxValue::Str(s)
+    }
+}
+impl From<&str> for LoxValue
+{
+    fn from(s: &str) -> Self
+    {
+        LoxValue::Str(s.to_string())
+    }
+}
+impl From<()> for LoxValue
+{
+    fn from(_: ()) -> Self
+    {
+        LoxValue::Nil
+    }
+}
+//None becomes nil
+impl<T: Into<LoxValue>> From<Option<T>> for LoxValue
+{
+    fn from(o: Option<T>) -> Self
+    {
+        match o {
+            Some(v) => v.into(),
+            None => LoxValue::Nil,
+        }
+    }
+}
+impl<T: Into<LoxValue>> From<Vec<T>> for LoxValue
+{
+    fn from(v: Vec<T>) -> Self
+    {
+        LoxValue::List(LoxList::new(v.into_iter().map(|e| e.into()).collect()))
+    }
+}
+
+impl TryFrom<LoxValue> for f64
+{
+    type Error = LoxTypeMismatch;
+    fn try_from(value: LoxValue) -> Result<Self, Self::Error>
+    {
+        match value {
+            LoxValue::Num(n) => Ok(n),
+            x => Err(LoxTypeMismatch::new(x.into(), LoxType::Num)),
+        }
+    }
+}
+impl TryFrom<LoxValue> for bool
+{
+    type Error = LoxTypeMismatch;
+    fn try_from(value: LoxValue) -> Result<Self, Self::Error>
+    {
+        match value {
+            LoxValue::Bool(b) => Ok(b),
+            x => Err(LoxTypeMismatch::new(x.into(), LoxType::Bool)),
+        }
+    }
+}
+impl TryFrom<LoxValue> for String
+{
+    type Error = LoxTypeMismatch;
+    fn try_from(value: LoxValue) -> Result<Self, Self::Error>
+    {
+        match value {
+            LoxValue::Str(s) => Ok(s),
+            x => Err(LoxTypeMismatch::new(x.into(), LoxType::Str)),
+        }
+    }
+}
+//Copies the elements out of a list
+impl TryFrom<LoxValue> for Vec<LoxValue>
+{
+    type Error = LoxTypeMismatch;
+    fn try_from(value: LoxValue) -> Result<Self, Self::Error>
+    {
+        match value {
+            LoxValue::List(l) => Ok(l.elements.borrow().clone()),
+            x => Err(LoxTypeMismatch::new(x.into(), LoxType::List)),
+        }
+    }
+}
@@ -2,9 +2,10 @@ use std::fmt::Display;
 use std::fmt;
 
 use super::loxType::LoxType;
-use super::loxValue::{LoxValue, LoxFn};
-use super::loxRuntimeError::{LoxRuntimeError, LoxRuntimeErrorType, LoxVariableError, LoxTypeError, loxTypeError::LoxTypeMismatch, LoxFunctionError};
+use super::loxValue::{LoxValue, LoxFn, LoxNative, LoxClass, LoxInstance, LoxList, LoxMap, LoxModule};
+use super::loxRuntimeError::{LoxRuntimeError, StackFrame, FrameKind, LoxRuntimeErrorType, LoxVariableError, LoxTypeError, loxTypeError::LoxTypeMismatch, LoxFunctionError, LoxPropertyError, LoxClassError, LoxIndexError, LoxImportError};
 use super::loxType::LoxCallee;
+use super::helpers::stringify;
 
 impl Display for LoxType
 {
@@ -12,6 +13,11 @@ impl Display for LoxType
     {
         match self {
             Self::Function(c) => write!(f, "Function with arity {}", c),
+            Self::Class => write!(f, "Class"),
+            Self::Instance => write!(f, "Instance"),
+            Self::List => write!(f, "List"),
+            Self::Map => write!(f, "Map"),
+            Self::Module => write!(f, "Module"),
             Self::Num => write!(f, "Num"),
             Self::Str => write!(f, "Str"),
             Self::Bool => write!(f, "Bool"),
@@ -27,7 +33,7 @@ impl Display for LoxTypeMismatch
         let mut expected = self.expected.iter();
         string.push_str(format!("{}", expected.next().unwrap()).as_str());
         for loxType in expected {
-            string.push_str(format!("or {}", loxType).as_str());
+            string.push_str(format!(" or {}", loxType).as_str());
         }
         write!(f, "{}, but found {}", string, self.found)
     }
@@ -60,6 +66,12 @@ impl Display for LoxRuntimeErrorType
             Self::VariableError(e) => format!("{}", e),
             Self::TypeError(e) => format!("{}", e),
             Self::FunctionError(e) => format!("{}", e),
+            Self::PropertyError(e) => format!("{}", e),
+            Self::ClassError(e) => format!("{}", e),
+            Self::IndexError(e) => format!("{}", e),
+            Self::ImportError(e) => format!("{}", e),
+            Self::Thrown(v) => format!("Uncaught exception: {}", stringify(v)),
+            Self::Exit(code) => format!("Exited with code {}", code),
         };
         write!(f, "{}", string)
     }
@@ -69,9 +81,54 @@ impl Display for LoxFunctionError
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
     {
         let string = match self {
-            Self::ArgPrmCountMismatch(a, p) => format!("Expected {} arguments but found {}", a, p),
+            Self::ArgPrmCountMismatch(a, p) => format!("Expected {} arguments but found {}", p, a),
             Self::NotCallable(e) => format!("Expression {} is not callable", e),
-            Self::NonFnReturn => "Cannot return from outside a function".to_string(),
+            Self::InvalidExitCode(n) => format!("Exit code {} is not a whole number from 0 to 255", n),
+        };
+        write!(f, "{}", string)
+    }
+}
+impl Display for LoxPropertyError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let string = match self {
+            Self::Undefined(p) => format!("Property {} does not exist", p),
+        };
+        write!(f, "{}", string)
+    }
+}
+impl Display for LoxClassError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let string = match self {
+            Self::SuperclassNotClass(v) => format!("Superclass must be a class, but found {}", v),
+            Self::SuperOutsideSubclass => r#"Cannot use "super" outside of a subclass"#.to_string(),
+        };
+        write!(f, "{}", string)
+    }
+}
+impl Display for LoxIndexError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let string = match self {
+            Self::OutOfRange(i, l) => format!("Index {} is out of range for a list of length {}", i, l),
+            Self::NotInteger(i) => format!("Index {} is not a whole number", i),
+            Self::MissingKey(k) => format!("Key {} does not exist", k),
+        };
+        write!(f, "{}", string)
+    }
+}
+impl Display for LoxImportError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let string = match self {
+            Self::NotFound(p, e) => format!("Cannot import {}: {}", p, e),
+            Self::Cycle(c) => format!("Circular import: {}", c.join(" -> ")),
+            Self::Invalid(p, _) => format!("Module {} contains errors", p),
         };
         write!(f, "{}", string)
     }
@@ -80,14 +137,71 @@ impl Display for LoxRuntimeError
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
     {
-        write!(f, "Error on line {}: {}", self.line, self.errorType,)
+        write!(f, "Error on line {}, column {}: {}", self.span.line, self.span.column, self.errorType,)
+    }
+}
+impl Display for StackFrame
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match &self.kind {
+            FrameKind::Function(name) => write!(f, "[line {}] in {}()", self.span.line, name),
+            FrameKind::Module(name) => write!(f, "[line {}] in module {}", self.span.line, name),
+            FrameKind::Script => write!(f, "[line {}] in script", self.span.line),
+        }
     }
 }
 impl Display for LoxFn
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
     {
-        write!(f, "Function with arity {}", self.arity())
+        write!(f, "Function {} with arity {}", self.name, self.arity())
+    }
+}
+impl Display for LoxNative
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "Native function {} with arity {}", self.name, self.arity())
+    }
+}
+impl Display for LoxClass
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.name)
+    }
+}
+impl Display for LoxInstance
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{} instance", self.class.name)
+    }
+}
+impl Display for LoxModule
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "Module {}", self.name)
+    }
+}
+impl Display for LoxList
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let elements: Vec<String> = self.elements.borrow().iter().map(|e| format!("{}", e)).collect();
+        write!(f, "[{}]", elements.join(", "))
+    }
+}
+impl Display for LoxMap
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let entries: Vec<String> = self.keys().into_iter().zip(self.values())
+            .map(|(k, v)| format!("{}: {}", LoxValue::from(k), v))
+            .collect();
+        write!(f, "{{{}}}", entries.join(", "))
     }
 }
 impl Display for LoxValue
@@ -99,8 +213,37 @@ impl Display for LoxValue
             Self::Str(s) => format!(r#""{}""#, s),
             Self::Bool(b) => format!("{}", b),
             Self::Function(f) => format!("{}", f),
+            Self::Native(n) => format!("{}", n),
+            Self::Class(c) => format!("{}", c),
+            Self::Instance(i) => format!("{}", i),
+            Self::List(l) => format!("{}", l),
+            Self::Map(m) => format!("{}", m),
+            Self::Module(m) => format!("{}", m),
             Self::Nil => "nil".to_string(),
         };
         write!(f, "{}", string)
     }
 }
+//Functions, classes and the shared containers show as Lox prints them, rather than
+//exposing their environments and bodies
+impl fmt::Debug for LoxValue
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            Self::Num(n) => write!(f, "Num({:?})", n),
+            Self::Str(s) => write!(f, "Str({:?})", s),
+            Self::Bool(b) => write!(f, "Bool({:?})", b),
+            Self::Nil => write!(f, "Nil"),
+            Self::Function(_) => write!(f, "Function({})", self),
+            Self::Native(_) => write!(f, "Native({})", self),
+            Self::Class(_) => write!(f, "Class({})", self),
+            Self::Instance(_) => write!(f, "Instance({})", self),
+            Self::List(_) => write!(f, "List({})", self),
+            Self::Map(_) => write!(f, "Map({})", self),
+            Self::Module(_) => write!(f, "Module({})", self),
+        }
+    }
+}
+impl std::error::Error for LoxRuntimeError {}
+impl std::error::Error for LoxTypeMismatch {}
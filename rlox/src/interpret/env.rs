@@ -1,66 +1,148 @@
 use std::collections::HashMap;
-use super::{LoxValue, LoxFn};
-use crate::expr::stmt::{Decl, Stmt};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::io::Write;
+use super::LoxValue;
+use super::loxValue::{LoxNative, LoxClass};
+use super::loxRuntimeError::LoxRuntimeErrorType;
+use super::natives;
+use super::module::Modules;
 
+//A handle to a scope. Cloning the handle shares the scope, so closures see
+//each other's writes to the variables they capture
 #[derive(Clone)]
 pub struct Env
+{
+    scope: Rc<RefCell<Scope>>,
+    modules: Rc<RefCell<Modules>>,
+    //Where "print" writes, shared by the whole program
+    output: Rc<RefCell<Box<dyn Write>>>,
+    //The command line arguments given to the script
+    args: Rc<RefCell<Vec<String>>>,
+    //The class of the built-in errors a catch clause receives, shared by the whole program
+    errorClass: Rc<LoxClass>,
+}
+
+struct Scope
 {
     env: HashMap<String, LoxValue>,
-    enclosing: Option<Box<Env>>,
+    enclosing: Option<Env>,
 }
+
 impl Env
 {
+    //Creates the outermost scope, with the built-in functions already defined
     pub fn global() -> Env
     {
-        Env{ env: HashMap::new(), enclosing: None }
+        let output: Box<dyn Write> = Box::new(std::io::stdout());
+        let env = Env{ scope: Env::scope(None), modules: Rc::default(), output: Rc::new(RefCell::new(output)), args: Rc::default(), errorClass: Rc::new(LoxClass::new("Error".to_string(), None, HashMap::new())) };
+        natives::register(&env);
+        env
+    }
+    //Creates the outermost scope of an imported file, which shares the loaded modules, output and arguments
+    pub fn module(&self) -> Env
+    {
+        let env = Env{ scope: Env::scope(None), ..self.clone() };
+        natives::register(&env);
+        env
     }
     pub fn new(enclosing: Env) -> Env
     {
-        Env{ env: HashMap::new(), enclosing: Some(Box::new(enclosing)) }
+        Env{ scope: Env::scope(Some(enclosing.clone())), ..enclosing }
+    }
+    fn scope(enclosing: Option<Env>) -> Rc<RefCell<Scope>>
+    {
+        Rc::new(RefCell::new(Scope{ env: HashMap::new(), enclosing }))
+    }
+    //Redirects the output of every environment in the program
+    pub fn setOutput(&self, output: Box<dyn Write>)
+    {
+        *self.output.borrow_mut() = output;
+    }
+    pub fn print(&self, text: &str)
+    {
+        let mut output = self.output.borrow_mut();
+        let _ = writeln!(output, "{}", text);
+        let _ = output.flush();
+    }
+    pub fn args(&self) -> &Rc<RefCell<Vec<String>>>
+    {
+        &self.args
+    }
+    pub fn errorClass(&self) -> &LoxClass
+    {
+        &self.errorClass
     }
-    pub fn end(self) -> Option<Box<Env>>
+    //Every file loaded by the program this environment belongs to
+    pub fn modules(&self) -> &Rc<RefCell<Modules>>
     {
-        self.enclosing
+        &self.modules
     }
-    pub fn get(&self, k: &String) -> Option<&LoxValue>
+    pub fn same(&self, other: &Env) -> bool
     {
-        match self.env.get(k) {
-            Some(v) => Some(v),
+        Rc::ptr_eq(&self.scope, &other.scope)
+    }
+    pub fn get(&self, k: &String) -> Option<LoxValue>
+    {
+        let scope = self.scope.borrow();
+        match scope.env.get(k) {
+            Some(v) => Some(v.clone()),
             None => {
-                match &self.enclosing {
+                match &scope.enclosing {
                     Some(p) => p.get(k),
                     None => None,
                 }
             },
         }
     }
-    pub fn assign(&mut self, k: String, v: LoxValue) -> Option<LoxValue>
+    fn ancestor(&self, depth: usize) -> Option<Env>
     {
-        match self.env.contains_key(&k) {
-            true => self.env.insert(k, v),
-            false => {
-                match &mut self.enclosing {
-                    Some(p) => p.assign(k, v),
-                    None => self.env.insert(k, v),
-                }
-            },
+        match depth {
+            0 => Some(self.clone()),
+            _ => self.scope.borrow().enclosing.as_ref().and_then(|p| p.ancestor(depth-1)),
         }
     }
-    pub fn decl(&mut self, k: String, v: LoxValue) -> Option<LoxValue>
+    fn globals(&self) -> Env
     {
-        self.env.insert(k, v)
+        match &self.scope.borrow().enclosing {
+            Some(p) => p.globals(),
+            None => self.clone(),
+        }
     }
-    pub fn contains_key(&self, k: &String) -> bool
+    //Looks a variable up using the depth computed by the resolver, or in the globals if it has none
+    pub fn getAt(&self, depth: Option<usize>, k: &String) -> Option<LoxValue>
     {
-        match self.env.contains_key(k) {
-            true => true,
-            false => {
-                match &self.enclosing {
-                    Some(p) => p.contains_key(k),
-                    None => false,
-                }
-            },
-        }
+        let env = match depth {
+            Some(d) => self.ancestor(d)?,
+            None => self.globals(),
+        };
+        let scope = env.scope.borrow();
+        scope.env.get(k).cloned()
+    }
+    //Returns None if the variable has not been declared
+    pub fn assignAt(&self, depth: Option<usize>, k: String, v: LoxValue) -> Option<LoxValue>
+    {
+        let env = match depth {
+            Some(d) => self.ancestor(d)?,
+            None => self.globals(),
+        };
+        let mut scope = env.scope.borrow_mut();
+        scope.env.get_mut(&k).map(|old| std::mem::replace(old, v))
+    }
+    //The variables declared directly in this scope, sorted by name
+    pub fn variables(&self) -> Vec<(String, LoxValue)>
+    {
+        let mut variables: Vec<(String, LoxValue)> = self.scope.borrow().env.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        variables.sort_by(|a, b| a.0.cmp(&b.0));
+        variables
+    }
+    pub fn decl(&self, k: String, v: LoxValue) -> Option<LoxValue>
+    {
+        self.scope.borrow_mut().env.insert(k, v)
+    }
+    //Makes a Rust closure callable from Lox under the given name
+    pub fn defineNative(&self, name: &str, arity: u32, function: impl Fn(Vec<LoxValue>) -> Result<LoxValue, LoxRuntimeErrorType> + 'static)
+    {
+        self.decl(name.to_string(), LoxValue::Native(LoxNative::new(name.to_string(), arity, function)));
     }
 }
@@ -1,6 +1,8 @@
-use super::loxValue::LoxValue;
-use super::loxRuntimeError::{LoxRuntimeError, loxTypeError::{LoxTypeError, LoxTypeMismatch}};
-use super::loxType::LoxType;
+use super::loxValue::{LoxValue, LoxKey, LoxClass, LoxInstance};
+use super::loxRuntimeError::{LoxRuntimeError, LoxRuntimeErrorType, LoxFunctionError, LoxIndexError, loxTypeError::{LoxTypeError, LoxTypeMismatch}};
+use super::loxType::{LoxType, LoxCallee};
+use crate::span::Span;
+use crate::expr::Parameter;
 
 pub fn truthy(value: &LoxValue) -> bool
 {
@@ -9,10 +11,25 @@ pub fn truthy(value: &LoxValue) -> bool
         LoxValue::Str(_) => true,
         LoxValue::Bool(b) => *b,
         LoxValue::Function(_) => true,
+        LoxValue::Native(_) => true,
+        LoxValue::Class(_) => true,
+        LoxValue::Instance(_) => true,
+        LoxValue::List(_) => true,
+        LoxValue::Map(_) => true,
+        LoxValue::Module(_) => true,
         LoxValue::Nil => false,
     }
 }
 
+//Converts a value to the text "print" would show, but without quoting strings
+pub fn stringify(value: &LoxValue) -> String
+{
+    match value {
+        LoxValue::Str(s) => s.clone(),
+        _ => format!("{}", value),
+    }
+}
+
 pub fn equal(v1: &LoxValue, v2: &LoxValue) -> bool
 {
     match (v1, v2) {
@@ -20,26 +37,84 @@ pub fn equal(v1: &LoxValue, v2: &LoxValue) -> bool
         (LoxValue::Str(s1), LoxValue::Str(s2)) => s1==s2,
         (LoxValue::Bool(b1), LoxValue::Bool(b2)) => b1==b2,
         (LoxValue::Nil, LoxValue::Nil,) => true,
+        (LoxValue::Class(c1), LoxValue::Class(c2)) => c1.same(c2),
+        (LoxValue::Instance(i1), LoxValue::Instance(i2)) => i1.same(i2),
+        (LoxValue::List(l1), LoxValue::List(l2)) => l1.same(l2),
+        (LoxValue::Map(m1), LoxValue::Map(m2)) => m1.same(m2),
+        (LoxValue::Module(m1), LoxValue::Module(m2)) => m1.same(m2),
         _ => false
     }
 }
-fn num_mismatch_err(x: LoxValue, line: u32) -> LoxRuntimeError
+fn num_mismatch_err(x: LoxValue, span: Span) -> LoxRuntimeError
 {
-    LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Num)).into(), line)
+    LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Num)).into(), span)
 }
-pub fn binary_num_op(l: LoxValue, r: LoxValue, o: impl Fn(f64, f64) -> f64, line: u32) -> Result<LoxValue, LoxRuntimeError>
+#[allow(clippy::result_large_err)]
+pub fn binary_num_op(l: LoxValue, r: LoxValue, o: impl Fn(f64, f64) -> f64, spans: (Span, Span)) -> Result<LoxValue, LoxRuntimeError>
 {
     match (l, r) {
         (LoxValue::Num(n1), LoxValue::Num(n2)) => Ok(LoxValue::Num(o(n1, n2))),
-        (LoxValue::Num(_), x) => Err(num_mismatch_err(x, line)),
-        (x, _) => Err(num_mismatch_err(x, line)),
+        (LoxValue::Num(_), x) => Err(num_mismatch_err(x, spans.1)),
+        (x, _) => Err(num_mismatch_err(x, spans.0)),
     }
 }
-pub fn binary_cmp_op(l: LoxValue, r: LoxValue, o: impl Fn(f64, f64) -> bool, line: u32) -> Result<LoxValue, LoxRuntimeError>
+#[allow(clippy::result_large_err)]
+pub fn binary_cmp_op(l: LoxValue, r: LoxValue, o: impl Fn(f64, f64) -> bool, spans: (Span, Span)) -> Result<LoxValue, LoxRuntimeError>
 {
     match (l, r) {
         (LoxValue::Num(n1), LoxValue::Num(n2)) => Ok(LoxValue::Bool(o(n1, n2))),
-        (LoxValue::Num(_), x) => Err(num_mismatch_err(x, line)),
-        (x, _) => Err(num_mismatch_err(x, line)),
+        (LoxValue::Num(_), x) => Err(num_mismatch_err(x, spans.1)),
+        (x, _) => Err(num_mismatch_err(x, spans.0)),
     }
 }
+#[allow(clippy::result_large_err)]
+pub fn call(mut callee: impl LoxCallee, arguments: Vec<LoxValue>, span: Span) -> Result<LoxValue, LoxRuntimeError>
+{
+    if callee.arity() != arguments.len() as u32 {
+        return Err(LoxRuntimeError::new(LoxFunctionError::ArgPrmCountMismatch(arguments.len() as u32, callee.arity()).into(), span));
+    }
+    callee.call(arguments, span)
+}
+//The value a catch clause binds for an error. Thrown values are caught as they
+//are, while built-in errors become an instance of errorClass describing them
+pub fn caughtValue(error: LoxRuntimeError, errorClass: &LoxClass) -> LoxValue
+{
+    match error.errorType {
+        LoxRuntimeErrorType::Thrown(v) => v,
+        errorType => {
+            let instance = LoxInstance::new(errorClass.clone());
+            instance.set("message".to_string(), LoxValue::Str(format!("{}", errorType)));
+            instance.set("line".to_string(), LoxValue::Num(error.span.line as f64));
+            instance.set("kind".to_string(), LoxValue::Str(errorType.kind().to_string()));
+            LoxValue::Instance(instance)
+        },
+    }
+}
+//Checks that a value can index a list of the given length
+pub fn listIndex(index: &LoxValue, len: usize) -> Result<usize, LoxRuntimeErrorType>
+{
+    match index {
+        LoxValue::Num(n) if n.fract() != 0.0 => Err(LoxIndexError::NotInteger(*n).into()),
+        LoxValue::Num(n) if *n < 0.0 || *n >= len as f64 => Err(LoxIndexError::OutOfRange(*n, len).into()),
+        LoxValue::Num(n) => Ok(*n as usize),
+        x => Err(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.clone().into(), LoxType::Num)).into()),
+    }
+}
+//Converts a value to a map key, if it is of a hashable type
+pub fn mapKey(key: &LoxValue) -> Result<LoxKey, LoxRuntimeErrorType>
+{
+    match key {
+        //-0 and 0 compare equal, so they must be the same key
+        LoxValue::Num(n) if *n == 0.0 => Ok(LoxKey::Num(0f64.to_bits())),
+        LoxValue::Num(n) => Ok(LoxKey::Num(n.to_bits())),
+        LoxValue::Str(s) => Ok(LoxKey::Str(s.clone())),
+        LoxValue::Bool(b) => Ok(LoxKey::Bool(*b)),
+        LoxValue::Nil => Ok(LoxKey::Nil),
+        x => Err(LoxTypeError::Mismatch(LoxTypeMismatch::newMany(x.clone().into(), vec![LoxType::Str, LoxType::Num, LoxType::Bool, LoxType::Nil])).into()),
+    }
+}
+//The names a function binds its arguments to, which is all the interpreter needs of its parameters
+pub fn names(params: Vec<Parameter>) -> Vec<String>
+{
+    params.into_iter().map(|p| p.name).collect()
+}
@@ -0,0 +1,8 @@
+use super::super::LoxValue;
+
+#[derive(Debug)]
+pub enum LoxClassError
+{
+    SuperclassNotClass(LoxValue),
+    SuperOutsideSubclass,
+}
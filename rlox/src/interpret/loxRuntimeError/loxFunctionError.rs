@@ -1,8 +1,10 @@
 use super::super::LoxValue;
 
+#[derive(Debug)]
 pub enum LoxFunctionError
 {
     ArgPrmCountMismatch(u32, u32),
     NotCallable(LoxValue),
-    NonFnReturn,
+    //A status for "exit" that a process cannot end with
+    InvalidExitCode(f64),
 }
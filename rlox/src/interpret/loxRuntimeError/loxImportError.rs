@@ -0,0 +1,12 @@
+use crate::LoxStaticError;
+
+#[derive(Debug)]
+pub enum LoxImportError
+{
+    //The path and the reason it could not be read
+    NotFound(String, String),
+    //The chain of files, ending with the one imported again
+    Cycle(Vec<String>),
+    //The path and the errors found in it before it could run
+    Invalid(String, Vec<LoxStaticError>),
+}
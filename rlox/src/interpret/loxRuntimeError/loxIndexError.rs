@@ -0,0 +1,7 @@
+#[derive(Debug)]
+pub enum LoxIndexError
+{
+    OutOfRange(f64, usize),
+    NotInteger(f64),
+    MissingKey(String),
+}
@@ -1,10 +1,12 @@
 use super::super::loxType::LoxType;
 
+#[derive(Debug)]
 pub enum LoxTypeError
 {
     Mismatch(LoxTypeMismatch),
 }
 
+#[derive(Debug)]
 pub struct LoxTypeMismatch
 {
     pub found: LoxType,
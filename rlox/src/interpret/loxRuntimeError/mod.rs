@@ -1,29 +1,102 @@
+use crate::span::Span;
+use super::LoxValue;
+
 pub mod loxTypeError;
 pub use loxTypeError::LoxTypeError;
 pub mod loxVariableError;
 pub use loxVariableError::LoxVariableError;
 pub mod loxFunctionError;
 pub use loxFunctionError::LoxFunctionError;
+pub mod loxPropertyError;
+pub use loxPropertyError::LoxPropertyError;
+pub mod loxClassError;
+pub use loxClassError::LoxClassError;
+pub mod loxIndexError;
+pub use loxIndexError::LoxIndexError;
+pub mod loxImportError;
+pub use loxImportError::LoxImportError;
 
+#[derive(Debug)]
 pub struct LoxRuntimeError
 {
     pub errorType: LoxRuntimeErrorType,
-    pub line: u32,
+    pub span: Span,
+    //The calls that were active when the error happened, innermost first
+    pub trace: Vec<StackFrame>,
+    //Where execution was in the frame being unwound
+    site: Span,
 }
 
 impl LoxRuntimeError
 {
-    pub fn new(errorType: LoxRuntimeErrorType, line: u32) -> Self
+    pub fn new(errorType: LoxRuntimeErrorType, span: Span) -> Self
+    {
+        LoxRuntimeError{errorType, span, trace: Vec::new(), site: span}
+    }
+    //Records that the error escaped from a function call or import made at the given span
+    pub fn unwind(mut self, kind: FrameKind, call: Span) -> Self
+    {
+        self.trace.push(StackFrame{ kind, span: self.site });
+        self.site = call;
+        self
+    }
+    //Records the top level of the script as the outermost frame
+    pub fn finish(mut self) -> Self
     {
-        LoxRuntimeError{errorType, line}
+        self.trace.push(StackFrame{ kind: FrameKind::Script, span: self.site });
+        self
     }
 }
 
+//One call on the stack, with where execution was inside it
+#[derive(Debug)]
+pub struct StackFrame
+{
+    pub kind: FrameKind,
+    pub span: Span,
+}
+#[derive(Debug)]
+pub enum FrameKind
+{
+    Function(String),
+    //The top level of an imported file
+    Module(String),
+    Script,
+}
+
+#[derive(Debug)]
 pub enum LoxRuntimeErrorType
 {
     TypeError(LoxTypeError),
     VariableError(LoxVariableError),
     FunctionError(LoxFunctionError),
+    PropertyError(LoxPropertyError),
+    ClassError(LoxClassError),
+    IndexError(LoxIndexError),
+    ImportError(LoxImportError),
+    //A value passed to "throw" that nothing caught
+    Thrown(LoxValue),
+    //A call to "exit" with the status to end the process with. It cannot be
+    //caught, so it unwinds the whole program
+    Exit(i32),
+}
+impl LoxRuntimeErrorType
+{
+    //The name scripts see as the "kind" of a caught error
+    pub fn kind(&self) -> &'static str
+    {
+        match self {
+            Self::TypeError(_) => "TypeError",
+            Self::VariableError(_) => "VariableError",
+            Self::FunctionError(_) => "FunctionError",
+            Self::PropertyError(_) => "PropertyError",
+            Self::ClassError(_) => "ClassError",
+            Self::IndexError(_) => "IndexError",
+            Self::ImportError(_) => "ImportError",
+            Self::Thrown(_) => "Thrown",
+            Self::Exit(_) => "Exit",
+        }
+    }
 }
 
 impl From<LoxTypeError> for LoxRuntimeErrorType
@@ -47,3 +120,38 @@ impl From<LoxFunctionError> for LoxRuntimeErrorType
         LoxRuntimeErrorType::FunctionError(functionError)
     }
 }
+impl From<LoxPropertyError> for LoxRuntimeErrorType
+{
+    fn from(propertyError: LoxPropertyError) -> Self
+    {
+        LoxRuntimeErrorType::PropertyError(propertyError)
+    }
+}
+impl From<LoxClassError> for LoxRuntimeErrorType
+{
+    fn from(classError: LoxClassError) -> Self
+    {
+        LoxRuntimeErrorType::ClassError(classError)
+    }
+}
+impl From<LoxIndexError> for LoxRuntimeErrorType
+{
+    fn from(indexError: LoxIndexError) -> Self
+    {
+        LoxRuntimeErrorType::IndexError(indexError)
+    }
+}
+impl From<LoxImportError> for LoxRuntimeErrorType
+{
+    fn from(importError: LoxImportError) -> Self
+    {
+        LoxRuntimeErrorType::ImportError(importError)
+    }
+}
+impl From<loxTypeError::LoxTypeMismatch> for LoxRuntimeErrorType
+{
+    fn from(mismatch: loxTypeError::LoxTypeMismatch) -> Self
+    {
+        LoxRuntimeErrorType::TypeError(LoxTypeError::Mismatch(mismatch))
+    }
+}
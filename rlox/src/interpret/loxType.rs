@@ -1,10 +1,16 @@
 use super::loxValue::LoxValue;
 use super::LoxRuntimeError;
-use super::env::Env;
+use crate::span::Span;
 
+#[derive(Debug)]
 pub enum LoxType
 {
     Function(u32),
+    Class,
+    Instance,
+    List,
+    Map,
+    Module,
     Num,
     Str,
     Bool,
@@ -17,6 +23,12 @@ impl From<LoxValue> for LoxType
     {
         match value {
             LoxValue::Function(f) => LoxType::Function(f.arity()),
+            LoxValue::Native(n) => LoxType::Function(n.arity()),
+            LoxValue::Class(_) => LoxType::Class,
+            LoxValue::Instance(_) => LoxType::Instance,
+            LoxValue::List(_) => LoxType::List,
+            LoxValue::Map(_) => LoxType::Map,
+            LoxValue::Module(_) => LoxType::Module,
             LoxValue::Num(_) => LoxType::Num,
             LoxValue::Str(_) => LoxType::Str,
             LoxValue::Bool(_) => LoxType::Bool,
@@ -28,5 +40,6 @@ impl From<LoxValue> for LoxType
 pub trait LoxCallee
 {
     fn arity(&self) -> u32;
-    fn call(&mut self, arguments: Vec<LoxValue>) -> Result<LoxValue, LoxRuntimeError>;
+    #[allow(clippy::result_large_err)]
+    fn call(&mut self, arguments: Vec<LoxValue>, span: Span) -> Result<LoxValue, LoxRuntimeError>;
 }
@@ -1,6 +1,12 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
 use super::Decl;
 use super::LoxRuntimeError;
-use super::execute;
+use crate::span::Span;
+use super::loxRuntimeError::{LoxRuntimeErrorType, FrameKind};
+use super::{execute, Flow};
 use super::env::Env;
 use super::loxType::LoxCallee;
 
@@ -8,6 +14,12 @@ use super::loxType::LoxCallee;
 pub enum LoxValue
 {
     Function(LoxFn),
+    Native(LoxNative),
+    Class(LoxClass),
+    Instance(LoxInstance),
+    List(LoxList),
+    Map(LoxMap),
+    Module(LoxModule),
     Num(f64),
     Str(String),
     Bool(bool),
@@ -17,16 +29,24 @@ pub enum LoxValue
 #[derive(Clone)]
 pub struct LoxFn
 {
+    pub name: String,
     body: Vec<Decl>,
     arguments: Vec<String>,
     env: Env,
+    isInitialiser: bool,
 }
 impl LoxFn
 {
-    pub fn new(name: String, body: Vec<Decl>, arguments: Vec<String>, env: Env) -> LoxFn
+    pub fn new(name: String, body: Vec<Decl>, arguments: Vec<String>, env: Env, isInitialiser: bool) -> LoxFn
+    {
+        LoxFn{ name, body, arguments, env, isInitialiser }
+    }
+    //Returns a copy of the method with "this" bound to the instance
+    pub fn bind(&self, instance: LoxInstance) -> LoxFn
     {
-        let loxFn = LoxFn{ body, arguments, env };
-        loxFn
+        let env = Env::new(self.env.clone());
+        env.decl("this".to_string(), LoxValue::Instance(instance));
+        LoxFn{ env, ..self.clone() }
     }
 }
 
@@ -36,16 +56,259 @@ impl LoxCallee for LoxFn
     {
         self.arguments.len() as u32
     }
-    fn call(&mut self, parameters: Vec<LoxValue>) -> Result<LoxValue, LoxRuntimeError>
+    fn call(&mut self, parameters: Vec<LoxValue>, span: Span) -> Result<LoxValue, LoxRuntimeError>
     {
-        let mut locEnv = Env::new(self.env.clone());
+        let locEnv = Env::new(self.env.clone());
         for (i, parameter) in parameters.into_iter().enumerate() {
             locEnv.decl(self.arguments[i].clone(), parameter);
         }
-        let mut val = None;
-        match execute(self.body.clone(), locEnv, &mut val) {
-            Ok(e) => { self.env = *e.end().unwrap(); Ok(val.unwrap_or(LoxValue::Nil)) },
-            Err(e) => Err(e),
+        let flow = execute(self.body.clone(), &locEnv).map_err(|e| e.unwind(FrameKind::Function(self.name.clone()), span))?;
+        if self.isInitialiser {
+            return Ok(self.env.get(&"this".to_string()).unwrap_or(LoxValue::Nil));
+        }
+        match flow {
+            Flow::Return(v) => Ok(v),
+            _ => Ok(LoxValue::Nil),
+        }
+    }
+}
+
+pub type NativeFn = dyn Fn(Vec<LoxValue>) -> Result<LoxValue, LoxRuntimeErrorType>;
+
+//A function implemented in Rust. Errors it returns are reported at the span of the call
+#[derive(Clone)]
+pub struct LoxNative
+{
+    pub name: String,
+    arity: u32,
+    function: Rc<NativeFn>,
+}
+impl LoxNative
+{
+    pub fn new(name: String, arity: u32, function: impl Fn(Vec<LoxValue>) -> Result<LoxValue, LoxRuntimeErrorType> + 'static) -> LoxNative
+    {
+        LoxNative{ name, arity, function: Rc::new(function) }
+    }
+}
+
+impl LoxCallee for LoxNative
+{
+    fn arity(&self) -> u32
+    {
+        self.arity
+    }
+    fn call(&mut self, parameters: Vec<LoxValue>, span: Span) -> Result<LoxValue, LoxRuntimeError>
+    {
+        (self.function)(parameters).map_err(|e| LoxRuntimeError::new(e, span))
+    }
+}
+
+#[derive(Clone)]
+pub struct LoxClass
+{
+    pub name: String,
+    pub superclass: Option<Box<LoxClass>>,
+    methods: Rc<HashMap<String, LoxFn>>,
+}
+impl LoxClass
+{
+    pub fn new(name: String, superclass: Option<LoxClass>, methods: HashMap<String, LoxFn>) -> LoxClass
+    {
+        LoxClass{ name, superclass: superclass.map(Box::new), methods: Rc::new(methods) }
+    }
+    //Looks the method up on this class, then on each superclass in turn
+    pub fn findMethod(&self, name: &str) -> Option<&LoxFn>
+    {
+        match self.methods.get(name) {
+            Some(m) => Some(m),
+            None => self.superclass.as_ref().and_then(|s| s.findMethod(name)),
+        }
+    }
+    pub fn same(&self, other: &LoxClass) -> bool
+    {
+        Rc::ptr_eq(&self.methods, &other.methods)
+    }
+    //Whether this is the class given or inherits from it
+    pub fn inherits(&self, other: &LoxClass) -> bool
+    {
+        self.same(other) || self.superclass.as_ref().is_some_and(|s| s.inherits(other))
+    }
+}
+
+impl LoxCallee for LoxClass
+{
+    fn arity(&self) -> u32
+    {
+        match self.findMethod("init") {
+            Some(i) => i.arity(),
+            None => 0,
+        }
+    }
+    fn call(&mut self, parameters: Vec<LoxValue>, span: Span) -> Result<LoxValue, LoxRuntimeError>
+    {
+        let instance = LoxInstance::new(self.clone());
+        if let Some(i) = self.findMethod("init") {
+            i.bind(instance.clone()).call(parameters, span)?;
+        }
+        Ok(LoxValue::Instance(instance))
+    }
+}
+
+//Instances are shared, so a copy of the value refers to the same fields
+#[derive(Clone)]
+pub struct LoxInstance
+{
+    pub class: LoxClass,
+    fields: Rc<RefCell<HashMap<String, LoxValue>>>,
+}
+impl LoxInstance
+{
+    pub fn new(class: LoxClass) -> LoxInstance
+    {
+        LoxInstance{ class, fields: Rc::new(RefCell::new(HashMap::new())) }
+    }
+    pub fn get(&self, name: &str) -> Option<LoxValue>
+    {
+        if let Some(v) = self.fields.borrow().get(name) {
+            return Some(v.clone());
+        }
+        self.class.findMethod(name).map(|m| LoxValue::Function(m.bind(self.clone())))
+    }
+    pub fn set(&self, name: String, value: LoxValue)
+    {
+        self.fields.borrow_mut().insert(name, value);
+    }
+    pub fn same(&self, other: &LoxInstance) -> bool
+    {
+        Rc::ptr_eq(&self.fields, &other.fields)
+    }
+}
+
+//The namespace value an import binds, giving access to the top-level declarations of a file
+#[derive(Clone)]
+pub struct LoxModule
+{
+    pub name: String,
+    env: Env,
+}
+impl LoxModule
+{
+    pub fn new(name: String, env: Env) -> LoxModule
+    {
+        LoxModule{ name, env }
+    }
+    pub fn get(&self, name: &String) -> Option<LoxValue>
+    {
+        self.env.get(name)
+    }
+    pub fn same(&self, other: &LoxModule) -> bool
+    {
+        self.env.same(&other.env)
+    }
+}
+
+//Lists are shared like instances, so passing one to a function lets it modify the original
+#[derive(Clone)]
+pub struct LoxList
+{
+    pub elements: Rc<RefCell<Vec<LoxValue>>>,
+}
+impl LoxList
+{
+    pub fn new(elements: Vec<LoxValue>) -> LoxList
+    {
+        LoxList{ elements: Rc::new(RefCell::new(elements)) }
+    }
+    pub fn len(&self) -> usize
+    {
+        self.elements.borrow().len()
+    }
+    pub fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+    pub fn same(&self, other: &LoxList) -> bool
+    {
+        Rc::ptr_eq(&self.elements, &other.elements)
+    }
+}
+
+//The values that can be used as map keys. Numbers are stored by their bits so they can be hashed
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum LoxKey
+{
+    Num(u64),
+    Str(String),
+    Bool(bool),
+    Nil,
+}
+impl From<LoxKey> for LoxValue
+{
+    fn from(key: LoxKey) -> Self
+    {
+        match key {
+            LoxKey::Num(n) => LoxValue::Num(f64::from_bits(n)),
+            LoxKey::Str(s) => LoxValue::Str(s),
+            LoxKey::Bool(b) => LoxValue::Bool(b),
+            LoxKey::Nil => LoxValue::Nil,
+        }
+    }
+}
+
+//Maps remember the order keys were first inserted in, so iterating over them is predictable
+#[derive(Clone, Default)]
+pub struct LoxMap
+{
+    entries: Rc<RefCell<HashMap<LoxKey, LoxValue>>>,
+    order: Rc<RefCell<Vec<LoxKey>>>,
+}
+impl LoxMap
+{
+    pub fn new() -> LoxMap
+    {
+        LoxMap::default()
+    }
+    pub fn get(&self, key: &LoxKey) -> Option<LoxValue>
+    {
+        self.entries.borrow().get(key).cloned()
+    }
+    pub fn set(&self, key: LoxKey, value: LoxValue)
+    {
+        if self.entries.borrow_mut().insert(key.clone(), value).is_none() {
+            self.order.borrow_mut().push(key);
         }
     }
+    pub fn remove(&self, key: &LoxKey) -> Option<LoxValue>
+    {
+        let value = self.entries.borrow_mut().remove(key);
+        if value.is_some() {
+            self.order.borrow_mut().retain(|k| k != key);
+        }
+        value
+    }
+    pub fn has(&self, key: &LoxKey) -> bool
+    {
+        self.entries.borrow().contains_key(key)
+    }
+    pub fn keys(&self) -> Vec<LoxKey>
+    {
+        self.order.borrow().clone()
+    }
+    pub fn values(&self) -> Vec<LoxValue>
+    {
+        let entries = self.entries.borrow();
+        self.order.borrow().iter().map(|k| entries[k].clone()).collect()
+    }
+    pub fn len(&self) -> usize
+    {
+        self.order.borrow().len()
+    }
+    pub fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+    pub fn same(&self, other: &LoxMap) -> bool
+    {
+        Rc::ptr_eq(&self.entries, &other.entries)
+    }
 }
@@ -1,6 +1,6 @@
 use crate::expr::*;
-use crate::expr::stmt::Stmt;
-use crate::expr::stmt::Decl;
+use crate::expr::stmt::{Stmt, StmtType, Decl, DeclType};
+use std::collections::HashMap;
 
 pub mod loxValue;
 use loxValue::*;
@@ -8,78 +8,186 @@ use loxValue::*;
 pub mod loxType;
 use loxType::*;
 
+pub mod conversions;
+
 pub mod display;
 
 pub mod env;
 use env::Env;
 
+pub mod module;
+
+pub mod output;
+
+mod natives;
+
 pub mod loxRuntimeError;
-use loxRuntimeError::{LoxRuntimeError, LoxTypeError, LoxVariableError, LoxFunctionError};
+use loxRuntimeError::{LoxRuntimeError, LoxRuntimeErrorType, LoxTypeError, LoxVariableError, LoxFunctionError, LoxPropertyError, LoxClassError, LoxIndexError};
 use loxRuntimeError::loxTypeError::LoxTypeMismatch;
 
 mod helpers;
 use helpers::*;
 
-pub fn execute(program: Vec<Decl>, mut env: Env, ret: &mut Option<LoxValue>) -> Result<Env, LoxRuntimeError>
+//How control leaves a statement. Anything other than Normal skips the rest of
+//the enclosing statements until a loop or function call handles it
+pub enum Flow
+{
+    Normal,
+    Return(LoxValue),
+    Break,
+    Continue,
+}
+
+//Runs a whole script, completing the stack trace of any error that escapes it.
+//Returns the value of the last statement if it is an expression, or nil
+#[allow(clippy::result_large_err)]
+pub fn interpret(mut program: Vec<Decl>, env: &Env) -> Result<LoxValue, LoxRuntimeError>
+{
+    let last = match program.last().map(|d| &d.declType) {
+        Some(DeclType::Stmt(Stmt{stmtType: StmtType::ExprStmt(_), ..})) => program.pop(),
+        _ => None,
+    };
+    let result = execute(program, env).and_then(|_| match last.map(|d| d.declType) {
+        Some(DeclType::Stmt(Stmt{stmtType: StmtType::ExprStmt(e), ..})) => evaluate(e, env),
+        _ => Ok(LoxValue::Nil),
+    });
+    result.map_err(|e| e.finish())
+}
+
+#[allow(clippy::result_large_err)]
+pub fn execute(program: Vec<Decl>, env: &Env) -> Result<Flow, LoxRuntimeError>
 {
     for decl in program {
-        match decl {
-            Decl::Stmt(stmt) => {
-                match stmt {
-                    Stmt::WhileStmt(c, b) => {
-                        let mut condition = evaluate(c.clone(), &mut env,)?;
-                        while truthy(&condition) {
-                            env = execute(vec![Decl::Stmt(*b.clone())], env, ret)?;
-                            if ret.is_some() { return Ok(env); }
-                            condition = evaluate(c.clone(), &mut env)?;
+        let span = decl.span;
+        match decl.declType {
+            DeclType::Stmt(stmt) => {
+                let flow = match stmt.stmtType {
+                    StmtType::WhileStmt(c, b) => repeat(Some(&c), &b, None, env)?,
+                    StmtType::ForStmt(f) => {
+                        let loopEnv = Env::new(env.clone());
+                        if let Some(i) = f.initialiser {
+                            execute(vec![*i], &loopEnv)?;
                         }
-                    }
-                    Stmt::IfStmt(c, i, e) => {
-                        let condition = evaluate(c.clone(), &mut env)?;
-                        match condition {
-                            LoxValue::Bool(true) => env = execute(vec![Decl::Stmt(*i)], env, ret)?,
-                            LoxValue::Bool(false) => {
-                                if let Some(x) = e {
-                                    env = execute(vec![Decl::Stmt(*x)], env, ret)?;
-                                }
-                            },
-                            _ => return Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(condition.into(), LoxType::Bool)).into(), c.line)),
+                        repeat(f.condition.as_ref(), &f.body, f.increment.as_ref(), &loopEnv)?
+                    },
+                    StmtType::IfStmt(c, i, e) => {
+                        let condition = evaluate(c, env)?;
+                        match (truthy(&condition), e) {
+                            (true, _) => execute(vec![Decl::new(DeclType::Stmt(*i), stmt.span)], env)?,
+                            (false, Some(x)) => execute(vec![Decl::new(DeclType::Stmt(*x), stmt.span)], env)?,
+                            (false, None) => Flow::Normal,
                         }
-                        if ret.is_some() { return Ok(env); }
                     },
-                    Stmt::PrintStmt(e) => {
-                        let value = evaluate(e, &mut env)?;
-                        println!("{}", value);
+                    StmtType::PrintStmt(e) => {
+                        let value = evaluate(e, env)?;
+                        env.print(stringify(&value).as_str());
+                        Flow::Normal
                     },
-                    Stmt::ExprStmt(e) => {
-                        let _ = evaluate(e, &mut env)?;
+                    StmtType::ExprStmt(e) => {
+                        let _ = evaluate(e, env)?;
+                        Flow::Normal
                     },
-                    Stmt::ReturnStmt(e) => {
-                        let value = evaluate(e, &mut env)?;
-                        *ret = Some(value);
+                    StmtType::ReturnStmt(Some(e)) => Flow::Return(evaluate(e, env)?),
+                    StmtType::ReturnStmt(None) => Flow::Return(LoxValue::Nil),
+                    StmtType::BreakStmt => Flow::Break,
+                    StmtType::ContinueStmt => Flow::Continue,
+                    StmtType::ThrowStmt(e) => {
+                        let value = evaluate(e, env)?;
+                        return Err(LoxRuntimeError::new(LoxRuntimeErrorType::Thrown(value), stmt.span));
                     },
-                    Stmt::Block(b) => {
-                        let mut newEnv = Env::new(env.clone());
-                        newEnv = execute(b, newEnv, ret)?;
-                        if ret.is_some() { return Ok(env); }
-                        env = *newEnv.end().unwrap();
-                    }
+                    StmtType::TryStmt(b, c, f) => {
+                        let result = match (execute(b, &Env::new(env.clone())), c) {
+                            (Err(e), Some((name, c))) if !matches!(e.errorType, LoxRuntimeErrorType::Exit(_)) => {
+                                let catchEnv = Env::new(env.clone());
+                                catchEnv.decl(name.name.clone(), caughtValue(e, env.errorClass()));
+                                execute(c, &catchEnv)
+                            },
+                            (result, _) => result,
+                        };
+                        //A finally block that leaves early overrides how the rest of the statement left
+                        if let Some(f) = f {
+                            match execute(f, &Env::new(env.clone()))? {
+                                Flow::Normal => (),
+                                flow => return Ok(flow),
+                            }
+                        }
+                        result?
+                    },
+                    StmtType::Block(b) => execute(b, &Env::new(env.clone()))?,
+                };
+                if !matches!(flow, Flow::Normal) {
+                    return Ok(flow);
                 }
             },
-            Decl::VarDecl(name, content) => {
-                let v = evaluate(content, &mut env)?;
+            DeclType::VarDecl(name, content) => {
+                let v = match content {
+                    Some(content) => evaluate(content, env)?,
+                    None => LoxValue::Nil,
+                };
                 env.decl(name.to_string(), v);
             },
-            Decl::FunDecl(name, arguments, body) => {
-                let f = LoxValue::Function(LoxFn::new(name.to_string(), body, arguments, env.clone()));
-                env.decl(name.to_string(), f);
+            DeclType::Import(path, name) => {
+                let module = module::import(&path, span, env)?;
+                env.decl(name, LoxValue::Module(module));
+            },
+            DeclType::FunDecl(func) => {
+                let name = func.name.clone();
+                let f = LoxValue::Function(LoxFn::new(func.name, func.body, names(func.params), env.clone(), false));
+                env.decl(name, f);
+            },
+            DeclType::ClassDecl(class) => {
+                let mut methodEnv = env.clone();
+                let superclass = match class.superclass {
+                    Some(s) => {
+                        let span = s.span;
+                        match evaluate(s, env)? {
+                            LoxValue::Class(c) => {
+                                methodEnv = Env::new(env.clone());
+                                methodEnv.decl("super".to_string(), LoxValue::Class(c.clone()));
+                                Some(c)
+                            },
+                            v => return Err(LoxRuntimeError::new(LoxClassError::SuperclassNotClass(v).into(), span)),
+                        }
+                    },
+                    None => None,
+                };
+                let mut classMethods = HashMap::new();
+                for method in class.methods {
+                    let isInitialiser = method.name == "init";
+                    classMethods.insert(method.name.clone(), LoxFn::new(method.name, method.body, names(method.params), methodEnv.clone(), isInitialiser));
+                }
+                let value = LoxValue::Class(LoxClass::new(class.name.clone(), superclass, classMethods));
+                env.decl(class.name, value);
             },
         }
     };
-    Ok(env)
+    Ok(Flow::Normal)
 }
 
-fn evaluate(value: Expr, env: &mut Env) -> Result<LoxValue, LoxRuntimeError>
+//Runs a loop body until the condition is falsey, or forever if there is none. The increment
+//runs after each pass, including those left with "continue"
+#[allow(clippy::result_large_err)]
+fn repeat(condition: Option<&Expr>, body: &Stmt, increment: Option<&Expr>, env: &Env) -> Result<Flow, LoxRuntimeError>
+{
+    let proceed = || match condition {
+        Some(c) => evaluate(c.clone(), env).map(|v| truthy(&v)),
+        None => Ok(true),
+    };
+    while proceed()? {
+        match execute(vec![Decl::new(DeclType::Stmt(body.clone()), body.span)], env)? {
+            Flow::Return(v) => return Ok(Flow::Return(v)),
+            Flow::Break => break,
+            Flow::Continue | Flow::Normal => (),
+        }
+        if let Some(i) = increment {
+            evaluate(i.clone(), env)?;
+        }
+    }
+    Ok(Flow::Normal)
+}
+
+#[allow(clippy::result_large_err)]
+fn evaluate(value: Expr, env: &Env) -> Result<LoxValue, LoxRuntimeError>
 {
     match value.exprType {
         ExprType::Literal(l) => {
@@ -90,49 +198,165 @@ fn evaluate(value: Expr, env: &mut Env) -> Result<LoxValue, LoxRuntimeError>
                 Literal::Nil => Ok(LoxValue::Nil),
             }
         },
-        ExprType::Variable(n) => {
-            match env.get(&n) {
-                Some(v) => Ok(v.clone()),
-                None => Err(LoxRuntimeError::new(LoxVariableError::Missing(n).into(), value.line))
+        ExprType::Variable(v) => {
+            match env.getAt(v.depth, &v.name) {
+                Some(v) => Ok(v),
+                None => Err(LoxRuntimeError::new(LoxVariableError::Missing(v.name).into(), value.span))
             }
         },
         ExprType::Assignment(a) => {
-            if env.contains_key(&a.id) {
+            if env.getAt(a.depth, &a.id).is_some() {
                 let v = evaluate(*a.expr, env)?;
-                env.assign(a.id, v.clone());
+                env.assignAt(a.depth, a.id, v.clone());
                 Ok(v)
             }
             else {
-                Err(LoxRuntimeError::new(LoxVariableError::Missing(a.id).into(), value.line))
+                Err(LoxRuntimeError::new(LoxVariableError::Missing(a.id).into(), value.span))
             }
         },
         ExprType::Call(c) => {
+            let calleeSpan = c.callee.span;
             let callee = evaluate(*c.callee, env)?;
             let mut evArgs: Vec<LoxValue> = Vec::with_capacity(c.arguments.len());
             for arg in c.arguments {
                 evArgs.push(evaluate(arg, env)?);
             }
-            for arg in &evArgs {
-                println!("arg {}", arg);
-            }
-            let retVal;
             match callee {
-                LoxValue::Function(mut f) if f.arity() == evArgs.len() as u32 => retVal = f.call(evArgs)?,
-                LoxValue::Function(f)  => return Err(LoxRuntimeError::new(LoxFunctionError::ArgPrmCountMismatch(evArgs.len() as u32, f.arity()).into(), value.line)),
-                _ => return Err(LoxRuntimeError::new(LoxFunctionError::NotCallable(callee).into(), value.line)),
+                LoxValue::Function(f) => call(f, evArgs, value.span),
+                LoxValue::Native(n) => call(n, evArgs, value.span),
+                LoxValue::Class(c) => call(c, evArgs, value.span),
+                _ => Err(LoxRuntimeError::new(LoxFunctionError::NotCallable(callee).into(), calleeSpan)),
+            }
+        },
+        ExprType::Get(g) => {
+            let objectSpan = g.object.span;
+            let object = evaluate(*g.object, env)?;
+            match object {
+                LoxValue::Instance(i) => {
+                    match i.get(&g.name) {
+                        Some(v) => Ok(v),
+                        None => Err(LoxRuntimeError::new(LoxPropertyError::Undefined(g.name).into(), value.span)),
+                    }
+                },
+                LoxValue::Module(m) => {
+                    match m.get(&g.name) {
+                        Some(v) => Ok(v),
+                        None => Err(LoxRuntimeError::new(LoxPropertyError::Undefined(g.name).into(), value.span)),
+                    }
+                },
+                _ => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::newMany(object.into(), vec![LoxType::Instance, LoxType::Module])).into(), objectSpan)),
+            }
+        },
+        ExprType::Set(s) => {
+            let objectSpan = s.object.span;
+            let object = evaluate(*s.object, env)?;
+            match object {
+                LoxValue::Instance(i) => {
+                    let v = evaluate(*s.value, env)?;
+                    i.set(s.name, v.clone());
+                    Ok(v)
+                },
+                _ => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(object.into(), LoxType::Instance)).into(), objectSpan)),
+            }
+        },
+        ExprType::This(depth) => {
+            match env.getAt(depth, &"this".to_string()) {
+                Some(v) => Ok(v),
+                None => Err(LoxRuntimeError::new(LoxVariableError::Missing("this".to_string()).into(), value.span)),
+            }
+        },
+        ExprType::Super(s) => {
+            //"this" is always bound in the scope just inside the one holding "super"
+            let (superclass, depth) = match (env.getAt(s.depth, &"super".to_string()), s.depth) {
+                (Some(LoxValue::Class(c)), Some(d)) if d > 0 => (c.clone(), d),
+                _ => return Err(LoxRuntimeError::new(LoxClassError::SuperOutsideSubclass.into(), value.span)),
+            };
+            let instance = match env.getAt(Some(depth-1), &"this".to_string()) {
+                Some(LoxValue::Instance(i)) => i,
+                _ => return Err(LoxRuntimeError::new(LoxClassError::SuperOutsideSubclass.into(), value.span)),
+            };
+            match superclass.findMethod(&s.method) {
+                Some(m) => Ok(LoxValue::Function(m.bind(instance))),
+                None => Err(LoxRuntimeError::new(LoxPropertyError::Undefined(s.method).into(), value.span)),
+            }
+        },
+        ExprType::List(l) => {
+            let mut elements = Vec::with_capacity(l.elements.len());
+            for element in l.elements {
+                elements.push(evaluate(element, env)?);
+            }
+            Ok(LoxValue::List(LoxList::new(elements)))
+        },
+        ExprType::Map(m) => {
+            let map = LoxMap::new();
+            for (k, v) in m.entries {
+                let span = k.span;
+                let key = evaluate(k, env)?;
+                let key = mapKey(&key).map_err(|e| LoxRuntimeError::new(e, span))?;
+                map.set(key, evaluate(v, env)?);
+            }
+            Ok(LoxValue::Map(map))
+        },
+        ExprType::Index(i) => {
+            let (objectSpan, indexSpan) = (i.object.span, i.index.span);
+            let object = evaluate(*i.object, env)?;
+            let index = evaluate(*i.index, env)?;
+            match object {
+                LoxValue::List(l) => {
+                    let n = listIndex(&index, l.len()).map_err(|e| LoxRuntimeError::new(e, indexSpan))?;
+                    Ok(l.elements.borrow()[n].clone())
+                },
+                LoxValue::Map(m) => {
+                    let key = mapKey(&index).map_err(|e| LoxRuntimeError::new(e, indexSpan))?;
+                    match m.get(&key) {
+                        Some(v) => Ok(v),
+                        None => Err(LoxRuntimeError::new(LoxIndexError::MissingKey(format!("{}", index)).into(), indexSpan)),
+                    }
+                },
+                _ => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::newMany(object.into(), vec![LoxType::List, LoxType::Map])).into(), objectSpan)),
+            }
+        },
+        ExprType::IndexSet(i) => {
+            let (objectSpan, indexSpan) = (i.object.span, i.index.span);
+            let object = evaluate(*i.object, env)?;
+            let index = evaluate(*i.index, env)?;
+            let v = evaluate(*i.value, env)?;
+            match object {
+                LoxValue::List(l) => {
+                    let n = listIndex(&index, l.len()).map_err(|e| LoxRuntimeError::new(e, indexSpan))?;
+                    l.elements.borrow_mut()[n] = v.clone();
+                    Ok(v)
+                },
+                LoxValue::Map(m) => {
+                    let key = mapKey(&index).map_err(|e| LoxRuntimeError::new(e, indexSpan))?;
+                    m.set(key, v.clone());
+                    Ok(v)
+                },
+                _ => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::newMany(object.into(), vec![LoxType::List, LoxType::Map])).into(), objectSpan)),
+            }
+        },
+        ExprType::Lambda(l) => Ok(LoxValue::Function(LoxFn::new("lambda".to_string(), l.body, names(l.params), env.clone(), false))),
+        ExprType::Interpolation(i) => {
+            let mut string = String::new();
+            for part in i.parts {
+                match part {
+                    Part::Text(s) => string += s.as_str(),
+                    Part::Value(v) => string += stringify(&evaluate(v, env)?).as_str(),
+                }
             }
-            Ok(retVal)
+            Ok(LoxValue::Str(string))
         },
         ExprType::Grouping(g) => evaluate(*g.expr, env),
         ExprType::Unary(u) => {
+            let operandSpan = u.expr.span;
             let result = evaluate(*u.expr, env)?;
             match u.operator {
                 Uoperator::Minus => {
-                    if let LoxValue::Bool(b) = result.clone() {
-                        Ok(LoxValue::Bool(!b))
+                    if let LoxValue::Num(n) = result {
+                        Ok(LoxValue::Num(-n))
                     }
                     else {
-                        Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(result.into(), LoxType::Num)).into(), value.line))
+                        Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(result.into(), LoxType::Num)).into(), operandSpan))
                     }
                 },
                 Uoperator::Bang => Ok(LoxValue::Bool(!truthy(&result))),
@@ -147,27 +371,28 @@ fn evaluate(value: Expr, env: &mut Env) -> Result<LoxValue, LoxRuntimeError>
             evaluate(*l.rexpr, env)
         },
         ExprType::Binary(b) => {
+            let spans = (b.lexpr.span, b.rexpr.span);
             let lresult = evaluate(*b.lexpr, env)?;                
             let rresult = evaluate(*b.rexpr, env)?;                
             match b.operator {
-                Boperator::Minus => binary_num_op(lresult, rresult, |x1, x2| {x1-x2}, value.line), Boperator::Plus => {
+                Boperator::Minus => binary_num_op(lresult, rresult, |x1, x2| {x1-x2}, spans), Boperator::Plus => {
                     match (lresult, rresult) {
                         (LoxValue::Num(n1), LoxValue::Num(n2)) => Ok(LoxValue::Num(n1+n2)),
                         (LoxValue::Str(s1), LoxValue::Str(s2)) => Ok(LoxValue::Str(s1+s2.as_str())),
-                        (LoxValue::Num(_), x) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Num)).into(), value.line)),
-                        (LoxValue::Str(_), x) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Str)).into(), value.line)),
-                        (x, LoxValue::Num(_)) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Num)).into(), value.line)),
-                        (x, LoxValue::Str(_)) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Str)).into(), value.line)),
+                        (LoxValue::Num(_), x) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Num)).into(), spans.1)),
+                        (LoxValue::Str(_), x) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Str)).into(), spans.1)),
+                        (x, LoxValue::Num(_)) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Num)).into(), spans.0)),
+                        (x, LoxValue::Str(_)) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::new(x.into(), LoxType::Str)).into(), spans.0)),
 
-                        (x, _) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::newMany(x.into(), vec![LoxType::Num, LoxType::Str])).into(), value.line)),
+                        (x, _) => Err(LoxRuntimeError::new(LoxTypeError::Mismatch(LoxTypeMismatch::newMany(x.into(), vec![LoxType::Num, LoxType::Str])).into(), spans.0)),
                     }
                 },
-            Boperator::Slash => binary_num_op(lresult, rresult, |x1, x2| {x1/x2}, value.line),
-            Boperator::Star => binary_num_op(lresult, rresult, |x1, x2| {x1*x2}, value.line),
-            Boperator::Less => binary_cmp_op(lresult, rresult, |x1, x2| {x1<x2}, value.line),
-            Boperator::LessEqual => binary_cmp_op(lresult, rresult, |x1, x2| {x1<=x2}, value.line),
-            Boperator::Greater => binary_cmp_op(lresult, rresult, |x1, x2| {x1<=x2}, value.line),
-            Boperator::GreaterEqual => binary_cmp_op(lresult, rresult, |x1, x2| {x1<=x2}, value.line),
+            Boperator::Slash => binary_num_op(lresult, rresult, |x1, x2| {x1/x2}, spans),
+            Boperator::Star => binary_num_op(lresult, rresult, |x1, x2| {x1*x2}, spans),
+            Boperator::Less => binary_cmp_op(lresult, rresult, |x1, x2| {x1<x2}, spans),
+            Boperator::LessEqual => binary_cmp_op(lresult, rresult, |x1, x2| {x1<=x2}, spans),
+            Boperator::Greater => binary_cmp_op(lresult, rresult, |x1, x2| {x1>x2}, spans),
+            Boperator::GreaterEqual => binary_cmp_op(lresult, rresult, |x1, x2| {x1>=x2}, spans),
             Boperator::EqualEqual=> Ok(LoxValue::Bool(equal(&lresult, &rresult))),
             Boperator::BangEqual => Ok(LoxValue::Bool(!equal(&lresult, &rresult))),
             }
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::scan::scan;
+use crate::parse::Parser;
+use crate::resolve::Resolver;
+use crate::optimize::optimize;
+use crate::span::Span;
+use super::env::Env;
+use super::execute;
+use super::loxValue::LoxModule;
+use super::loxRuntimeError::{LoxRuntimeError, LoxImportError, FrameKind};
+
+//Every file a program has loaded, shared by all of its environments
+#[derive(Default)]
+pub struct Modules
+{
+    //The name and text of each source, indexed by Span::source
+    pub sources: Vec<(String, String)>,
+    cache: HashMap<PathBuf, LoxModule>,
+    //Files whose top level is still running, outermost first, as their canonical path and name
+    loading: Vec<(PathBuf, String)>,
+}
+impl Modules
+{
+    //Returns the id spans in this source should carry
+    pub fn addSource(&mut self, name: &str, text: &str) -> u32
+    {
+        self.sources.push((name.to_string(), text.to_string()));
+        (self.sources.len() - 1) as u32
+    }
+    //Marks the main script as running, so modules importing it are reported as a cycle
+    pub fn enter(&mut self, name: &str)
+    {
+        if let Ok(path) = std::fs::canonicalize(name) {
+            self.loading.push((path, name.to_string()));
+        }
+    }
+}
+
+//Loads the file at path, relative to the file containing the import at span.
+//Each file only runs the first time it is imported
+#[allow(clippy::result_large_err)]
+pub fn import(path: &str, span: Span, env: &Env) -> Result<LoxModule, LoxRuntimeError>
+{
+    //Imports in code that has no file are relative to the working directory
+    let importer = env.modules().borrow().sources.get(span.source as usize).map(|s| s.0.clone()).unwrap_or_default();
+    let name = Path::new(&importer).parent().unwrap_or(Path::new("")).join(path).to_string_lossy().to_string();
+    let notFound = |e: std::io::Error| LoxRuntimeError::new(LoxImportError::NotFound(name.clone(), e.to_string()).into(), span);
+    let canonical = std::fs::canonicalize(&name).map_err(notFound)?;
+    {
+        let modules = env.modules().borrow();
+        if let Some(module) = modules.cache.get(&canonical) {
+            return Ok(module.clone());
+        }
+        if let Some(i) = modules.loading.iter().position(|(p, _)| *p == canonical) {
+            let mut chain: Vec<String> = modules.loading[i..].iter().map(|(_, n)| n.clone()).collect();
+            chain.push(name);
+            return Err(LoxRuntimeError::new(LoxImportError::Cycle(chain).into(), span));
+        }
+    }
+    let text = std::fs::read_to_string(&canonical).map_err(notFound)?;
+    let id = env.modules().borrow_mut().addSource(&name, &text);
+    let invalid = |errors| LoxRuntimeError::new(LoxImportError::Invalid(name.clone(), errors).into(), span);
+
+    let (tokens, errors) = scan(text, id);
+    if !errors.is_empty() { return Err(invalid(errors)); }
+    let (program, errors) = Parser::new(tokens).parse();
+    if !errors.is_empty() { return Err(invalid(errors)); }
+    let (program, errors) = Resolver::new().resolve(program);
+    if !errors.is_empty() { return Err(invalid(errors)); }
+
+    //The borrow must not be held while the module runs, as it may import others
+    let moduleEnv = env.module();
+    env.modules().borrow_mut().loading.push((canonical.clone(), name.clone()));
+    let result = execute(optimize(program), &moduleEnv);
+    env.modules().borrow_mut().loading.pop();
+    result.map_err(|e| e.unwind(FrameKind::Module(name.clone()), span))?;
+
+    let module = LoxModule::new(name, moduleEnv);
+    env.modules().borrow_mut().cache.insert(canonical, module.clone());
+    Ok(module)
+}
@@ -0,0 +1,138 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::env::Env;
+use super::loxValue::{LoxValue, LoxList};
+use super::loxType::LoxType;
+use super::loxRuntimeError::{LoxRuntimeErrorType, LoxFunctionError, LoxTypeError, LoxIndexError, loxTypeError::LoxTypeMismatch};
+use super::helpers::{stringify, listIndex, mapKey};
+
+fn mismatch(found: &LoxValue, expected: Vec<LoxType>) -> LoxRuntimeErrorType
+{
+    LoxTypeError::Mismatch(LoxTypeMismatch::newMany(found.clone().into(), expected)).into()
+}
+
+//Defines the built-in functions available to every script
+pub fn register(env: &Env)
+{
+    env.defineNative("clock", 0, |_| {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).expect("System clock is before the Unix epoch");
+        Ok(LoxValue::Num(time.as_secs_f64()))
+    });
+    env.defineNative("str", 1, |a| Ok(LoxValue::Str(stringify(&a[0]))));
+    env.defineNative("num", 1, |a| {
+        match &a[0] {
+            LoxValue::Num(n) => Ok(LoxValue::Num(*n)),
+            LoxValue::Str(s) => Ok(s.trim().parse::<f64>().map(LoxValue::Num).unwrap_or(LoxValue::Nil)),
+            LoxValue::Bool(b) => Ok(LoxValue::Num(if *b { 1.0 } else { 0.0 })),
+            x => Err(mismatch(x, vec![LoxType::Num, LoxType::Str, LoxType::Bool])),
+        }
+    });
+    env.defineNative("len", 1, |a| {
+        match &a[0] {
+            LoxValue::Str(s) => Ok(LoxValue::Num(s.chars().count() as f64)),
+            LoxValue::List(l) => Ok(LoxValue::Num(l.len() as f64)),
+            LoxValue::Map(m) => Ok(LoxValue::Num(m.len() as f64)),
+            x => Err(mismatch(x, vec![LoxType::Str, LoxType::List, LoxType::Map])),
+        }
+    });
+    env.defineNative("push", 2, |a| {
+        match &a[0] {
+            LoxValue::List(l) => {
+                l.elements.borrow_mut().push(a[1].clone());
+                Ok(LoxValue::Nil)
+            },
+            x => Err(mismatch(x, vec![LoxType::List])),
+        }
+    });
+    env.defineNative("pop", 1, |a| {
+        match &a[0] {
+            LoxValue::List(l) => l.elements.borrow_mut().pop().ok_or_else(|| LoxIndexError::OutOfRange(0.0, 0).into()),
+            x => Err(mismatch(x, vec![LoxType::List])),
+        }
+    });
+    env.defineNative("insert", 3, |a| {
+        match &a[0] {
+            LoxValue::List(l) => {
+                //Inserting at the end is allowed, anywhere past it is out of range
+                let i = match &a[1] {
+                    LoxValue::Num(n) if *n == l.len() as f64 => l.len(),
+                    index => listIndex(index, l.len())?,
+                };
+                l.elements.borrow_mut().insert(i, a[2].clone());
+                Ok(LoxValue::Nil)
+            },
+            x => Err(mismatch(x, vec![LoxType::List])),
+        }
+    });
+    env.defineNative("remove", 2, |a| {
+        match &a[0] {
+            LoxValue::List(l) => {
+                let i = listIndex(&a[1], l.len())?;
+                Ok(l.elements.borrow_mut().remove(i))
+            },
+            LoxValue::Map(m) => Ok(m.remove(&mapKey(&a[1])?).unwrap_or(LoxValue::Nil)),
+            x => Err(mismatch(x, vec![LoxType::List, LoxType::Map])),
+        }
+    });
+    env.defineNative("has", 2, |a| {
+        match &a[0] {
+            LoxValue::Map(m) => Ok(LoxValue::Bool(m.has(&mapKey(&a[1])?))),
+            x => Err(mismatch(x, vec![LoxType::Map])),
+        }
+    });
+    env.defineNative("keys", 1, |a| {
+        match &a[0] {
+            LoxValue::Map(m) => Ok(LoxValue::List(LoxList::new(m.keys().into_iter().map(LoxValue::from).collect()))),
+            x => Err(mismatch(x, vec![LoxType::Map])),
+        }
+    });
+    env.defineNative("values", 1, |a| {
+        match &a[0] {
+            LoxValue::Map(m) => Ok(LoxValue::List(LoxList::new(m.values()))),
+            x => Err(mismatch(x, vec![LoxType::Map])),
+        }
+    });
+    env.defineNative("type", 1, |a| {
+        let name = match &a[0] {
+            LoxValue::Function(_) => "Function",
+            LoxValue::Native(_) => "Function",
+            LoxValue::Class(_) => "Class",
+            LoxValue::Instance(_) => "Instance",
+            LoxValue::List(_) => "List",
+            LoxValue::Map(_) => "Map",
+            LoxValue::Module(_) => "Module",
+            LoxValue::Num(_) => "Num",
+            LoxValue::Str(_) => "Str",
+            LoxValue::Bool(_) => "Bool",
+            LoxValue::Nil => "Nil",
+        };
+        Ok(LoxValue::Str(name.to_string()))
+    });
+    //The class of the errors built-in operations raise, which scripts can also inherit from
+    env.decl("Error".to_string(), LoxValue::Class(env.errorClass().clone()));
+    env.defineNative("instanceOf", 2, |a| {
+        match (&a[0], &a[1]) {
+            (LoxValue::Instance(i), LoxValue::Class(c)) => Ok(LoxValue::Bool(i.class.inherits(c))),
+            (_, LoxValue::Class(_)) => Ok(LoxValue::Bool(false)),
+            (_, x) => Err(mismatch(x, vec![LoxType::Class])),
+        }
+    });
+    let args = env.args().clone();
+    env.defineNative("args", 0, move |_| {
+        Ok(LoxValue::List(LoxList::new(args.borrow().iter().map(|a| LoxValue::Str(a.clone())).collect())))
+    });
+    //Variables that are unset or not valid unicode are nil
+    env.defineNative("env", 1, |a| {
+        match &a[0] {
+            LoxValue::Str(name) => Ok(std::env::var(name).map(LoxValue::Str).unwrap_or(LoxValue::Nil)),
+            x => Err(mismatch(x, vec![LoxType::Str])),
+        }
+    });
+    //Finally blocks still run as the program unwinds, but nothing can catch it
+    env.defineNative("exit", 1, |a| {
+        match &a[0] {
+            LoxValue::Num(n) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => Err(LoxRuntimeErrorType::Exit(*n as i32)),
+            LoxValue::Num(n) => Err(LoxFunctionError::InvalidExitCode(*n).into()),
+            x => Err(mismatch(x, vec![LoxType::Num])),
+        }
+    });
+}
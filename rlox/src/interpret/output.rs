@@ -0,0 +1,38 @@
+use std::io::Write;
+use std::rc::Rc;
+use std::cell::RefCell;
+
+//An output that keeps what is written to it, for reading back after a script runs.
+//Clones share the same text
+#[derive(Clone, Default)]
+pub struct Capture
+{
+    buffer: Rc<RefCell<Vec<u8>>>,
+}
+impl Capture
+{
+    pub fn new() -> Capture
+    {
+        Capture::default()
+    }
+    pub fn text(&self) -> String
+    {
+        String::from_utf8_lossy(&self.buffer.borrow()).to_string()
+    }
+    pub fn clear(&self)
+    {
+        self.buffer.borrow_mut().clear();
+    }
+}
+impl Write for Capture
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize>
+    {
+        self.buffer.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()>
+    {
+        Ok(())
+    }
+}
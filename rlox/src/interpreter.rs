@@ -0,0 +1,185 @@
+use std::path::Path;
+use std::io::Write;
+use crate::scan::{scan, scanTrivia};
+use crate::parse::Parser;
+use crate::resolve::Resolver;
+use crate::optimize::optimize;
+use crate::lint::Linter;
+use crate::expr::stmt::Decl;
+use crate::diagnostic::{Diagnostic, Renderer};
+use crate::LoxStaticError;
+use crate::interpret::interpret;
+use crate::interpret::env::Env;
+use crate::interpret::loxValue::LoxValue;
+use crate::interpret::loxRuntimeError::{LoxRuntimeError, LoxRuntimeErrorType};
+
+//Everything that can stop a script
+#[derive(Debug)]
+pub enum LoxError
+{
+    //The errors found before the script could run
+    Static(Vec<LoxStaticError>),
+    Runtime(LoxRuntimeError),
+    //The path and the reason it could not be read
+    Io(String, std::io::Error),
+    //The script called "exit" with this status
+    Exit(i32),
+    //A syntax tree given as JSON did not have the right shape
+    Json(serde_json::Error),
+}
+impl std::fmt::Display for LoxError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self {
+            Self::Static(errors) => {
+                let lines: Vec<String> = errors.iter().map(|e| format!("{}", e)).collect();
+                write!(f, "{}", lines.join("\n"))
+            },
+            Self::Runtime(e) => write!(f, "{}", e),
+            Self::Io(path, e) => write!(f, "Cannot read {}: {}", path, e),
+            Self::Exit(code) => write!(f, "Exited with code {}", code),
+            Self::Json(e) => write!(f, "Invalid syntax tree: {}", e),
+        }
+    }
+}
+impl std::error::Error for LoxError
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+    {
+        match self {
+            Self::Runtime(e) => Some(e),
+            Self::Io(_, e) => Some(e),
+            Self::Json(e) => Some(e),
+            Self::Static(_) | Self::Exit(_) => None,
+        }
+    }
+}
+
+//A Lox program that Rust code can run scripts in. Globals persist between calls
+pub struct Interpreter
+{
+    env: Env,
+}
+impl Default for Interpreter
+{
+    fn default() -> Self
+    {
+        Interpreter::new()
+    }
+}
+#[allow(clippy::result_large_err)]
+impl Interpreter
+{
+    pub fn new() -> Self
+    {
+        Interpreter{ env: Env::global() }
+    }
+    //Runs source and returns the value of its last statement if that is an
+    //expression, or nil
+    pub fn eval_str(&mut self, source: &str) -> Result<LoxValue, LoxError>
+    {
+        self.eval(source, "<string>")
+    }
+    //Runs a script file. Imports in it are resolved relative to its directory
+    pub fn run_file(&mut self, path: impl AsRef<Path>) -> Result<LoxValue, LoxError>
+    {
+        let name = path.as_ref().to_string_lossy().to_string();
+        let source = std::fs::read_to_string(&path).map_err(|e| LoxError::Io(name.clone(), e))?;
+        self.enter(&name);
+        self.eval(&source, &name)
+    }
+    //Marks the file at path as the running script, so modules importing it are reported as a cycle
+    pub fn enter(&mut self, path: &str)
+    {
+        self.env.modules().borrow_mut().enter(path);
+    }
+    //Like eval_str, with name identifying the source in errors and imports
+    pub fn eval(&mut self, source: &str, name: &str) -> Result<LoxValue, LoxError>
+    {
+        let program = self.compile(source, name)?;
+        self.execute(program)
+    }
+    //Scans, parses and resolves source without running it
+    pub fn compile(&mut self, source: &str, name: &str) -> Result<Vec<Decl>, LoxError>
+    {
+        let id = self.env.modules().borrow_mut().addSource(name, source);
+        let (tokens, errors) = scan(source.to_string(), id);
+        if !errors.is_empty() { return Err(LoxError::Static(errors)); }
+        let (program, errors) = Parser::new(tokens).parse();
+        if !errors.is_empty() { return Err(LoxError::Static(errors)); }
+        let (program, errors) = Resolver::new().resolve(program);
+        if !errors.is_empty() { return Err(LoxError::Static(errors)); }
+        Ok(program)
+    }
+    //Compiles source without running it, returning the warnings the lints find in it
+    pub fn lint(&mut self, source: &str, name: &str) -> Result<Vec<LoxStaticError>, LoxError>
+    {
+        let program = self.compile(source, name)?;
+        let (_, comments, _) = scanTrivia(source.to_string(), 0);
+        let globals = self.globals().into_iter().map(|(name, _)| name).collect();
+        Ok(Linter::new(globals, &comments).lint(&program))
+    }
+    //Reads a program in the JSON form of Vec<Decl> and resolves it, ready for execute.
+    //Its spans do not refer to any file, so errors are reported by line and column only
+    pub fn compile_json(&mut self, json: &str) -> Result<Vec<Decl>, LoxError>
+    {
+        let program = serde_json::from_str(json).map_err(LoxError::Json)?;
+        let (program, errors) = Resolver::new().resolve(program);
+        if !errors.is_empty() { return Err(LoxError::Static(errors)); }
+        Ok(program)
+    }
+    //Runs a compiled program, after folding its constant expressions
+    pub fn execute(&mut self, program: Vec<Decl>) -> Result<LoxValue, LoxError>
+    {
+        interpret(optimize(program), &self.env).map_err(|e| match e.errorType {
+            LoxRuntimeErrorType::Exit(code) => LoxError::Exit(code),
+            _ => LoxError::Runtime(e),
+        })
+    }
+    //Sends the output of "print" to writer instead of stdout
+    pub fn set_output(&mut self, writer: impl Write + 'static)
+    {
+        self.env.setOutput(Box::new(writer));
+    }
+    //Sets the list the "args" native returns
+    pub fn set_args(&mut self, args: Vec<String>)
+    {
+        *self.env.args().borrow_mut() = args;
+    }
+    pub fn args(&self) -> Vec<String>
+    {
+        self.env.args().borrow().clone()
+    }
+    pub fn get_global(&self, name: &str) -> Option<LoxValue>
+    {
+        self.env.get(&name.to_string())
+    }
+    //Every global variable, including the built-in functions, sorted by name
+    pub fn globals(&self) -> Vec<(String, LoxValue)>
+    {
+        self.env.variables()
+    }
+    pub fn set_global(&mut self, name: &str, value: impl Into<LoxValue>)
+    {
+        self.env.decl(name.to_string(), value.into());
+    }
+    //Makes a Rust closure callable from scripts. Errors it returns are raised at the call
+    pub fn define_native(&mut self, name: &str, arity: u32, function: impl Fn(Vec<LoxValue>) -> Result<LoxValue, LoxRuntimeErrorType> + 'static)
+    {
+        self.env.defineNative(name, arity, function);
+    }
+    //Renders an error the way the command line shows it, quoting the source it came from
+    pub fn render(&self, error: &LoxError, renderer: &Renderer) -> String
+    {
+        let sources = &self.env.modules().borrow().sources;
+        match error {
+            LoxError::Static(errors) => {
+                let rendered: Vec<String> = errors.iter().map(|e| renderer.render(&Diagnostic::from(e), sources)).collect();
+                rendered.join("\n")
+            },
+            LoxError::Runtime(e) => renderer.render(&Diagnostic::from(e), sources),
+            LoxError::Io(..) | LoxError::Exit(_) | LoxError::Json(_) => format!("{}\n", error),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+#![allow(non_snake_case, non_camel_case_types)]
+pub mod span;
+pub mod token;
+pub mod scan;
+pub mod diagnostic;
+pub mod loxStaticError;
+pub use loxStaticError::LoxStaticError;
+pub mod expr;
+pub mod parse;
+pub mod resolve;
+pub mod optimize;
+pub mod lint;
+pub mod format;
+pub mod interpret;
+pub use interpret::loxValue::LoxValue;
+pub use interpret::output::Capture;
+pub use interpret::loxRuntimeError::{LoxRuntimeError, LoxRuntimeErrorType};
+
+pub mod interpreter;
+pub use interpreter::{Interpreter, LoxError};
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use crate::expr::*;
+use crate::expr::stmt::{Stmt, StmtType, Decl, DeclType, Class};
+use crate::optimize::constant;
+use crate::span::Span;
+use crate::token::Comment;
+use crate::LoxStaticError;
+use crate::loxStaticError::Severity;
+
+//Every lint, by the name "// lint: allow(name)" turns it off with
+pub const LINTS: [&str; 6] = ["unused", "shadow", "unreachable", "undeclared", "arity", "constant"];
+
+struct Binding
+{
+    //What to call it in warnings, such as "Variable" or "Parameter"
+    kind: &'static str,
+    //None for the globals the interpreter defines, which are not in the source
+    span: Option<Span>,
+    used: bool,
+    //The number of parameters, while it is known to hold a function declared with "fun"
+    arity: Option<usize>,
+    //A function declared later in its block, which only the functions nested before it can see
+    pending: bool,
+}
+
+//Warns about code that is valid but probably a mistake. Expects a program the resolver accepted
+pub struct Linter
+{
+    //The first scope holds the globals
+    scopes: Vec<HashMap<String, Binding>>,
+    //The index of the innermost function's first scope, or 0 outside any function
+    boundary: usize,
+    //The lints allowed on each line
+    allowed: HashMap<u32, Vec<String>>,
+    warnings: Vec<LoxStaticError>,
+}
+impl Linter
+{
+    //globals are the names defined before the program runs, such as the built-in functions
+    pub fn new(globals: Vec<String>, comments: &[Comment]) -> Self
+    {
+        let builtIn = globals.into_iter().map(|name| (name, Binding{ kind: "Global", span: None, used: true, arity: None, pending: false }));
+        let mut allowed: HashMap<u32, Vec<String>> = HashMap::new();
+        for comment in comments {
+            let list = comment.text.trim_start_matches('/').trim()
+                .strip_prefix("lint:")
+                .and_then(|rest| rest.trim().strip_prefix("allow("))
+                .and_then(|rest| rest.strip_suffix(')'));
+            if let Some(list) = list {
+                allowed.entry(comment.span.line).or_default().extend(list.split(',').map(|l| l.trim().to_string()));
+            }
+        }
+        Linter{ scopes: vec![builtIn.collect()], boundary: 0, allowed, warnings: Vec::new() }
+    }
+    //Returns the warnings in the order they appear in the source
+    pub fn lint(mut self, program: &[Decl]) -> Vec<LoxStaticError>
+    {
+        //Functions may refer to globals declared after them
+        for decl in program {
+            if let Some((name, kind, span, arity)) = declared(decl) {
+                self.scopes[0].insert(name.to_string(), Binding{ kind, span: Some(span), used: true, arity, pending: false });
+            }
+        }
+        self.decls(program);
+        self.warnings.sort_by(|a, b| a.span.start.cmp(&b.span.start).then_with(|| a.message.cmp(&b.message)));
+        self.warnings
+    }
+    fn warn(&mut self, warning: LoxStaticError)
+    {
+        let Severity::Warning(lint) = warning.severity else { return };
+        if self.allowed.get(&warning.span.line).is_some_and(|a| a.iter().any(|l| l == lint)) {
+            return;
+        }
+        self.warnings.push(warning);
+    }
+    fn decls(&mut self, decls: &[Decl])
+    {
+        //Local functions may call those declared after them, as the resolver allows
+        if self.scopes.len() > 1 {
+            for decl in decls {
+                if let DeclType::FunDecl(f) = &decl.declType {
+                    let binding = Binding{ kind: "Function", span: Some(f.span), used: false, arity: Some(f.params.len()), pending: true };
+                    self.scopes.last_mut().expect("There is always a global scope").entry(f.name.clone()).or_insert(binding);
+                }
+            }
+        }
+        let mut exit: Option<&str> = None;
+        for decl in decls {
+            if let Some(exit) = exit.take() {
+                self.warn(LoxStaticError::warning("unreachable", decl.span, format!("Unreachable code after \"{}\"", exit).as_str()));
+            }
+            self.decl(decl);
+            exit = match &decl.declType {
+                DeclType::Stmt(Stmt{ stmtType: StmtType::ReturnStmt(_), .. }) => Some("return"),
+                DeclType::Stmt(Stmt{ stmtType: StmtType::ThrowStmt(_), .. }) => Some("throw"),
+                DeclType::Stmt(Stmt{ stmtType: StmtType::BreakStmt, .. }) => Some("break"),
+                DeclType::Stmt(Stmt{ stmtType: StmtType::ContinueStmt, .. }) => Some("continue"),
+                _ => None,
+            };
+        }
+    }
+    fn decl(&mut self, decl: &Decl)
+    {
+        match &decl.declType {
+            DeclType::VarDecl(name, value) => {
+                self.declare(name, "Variable", decl.span, None);
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            },
+            DeclType::FunDecl(f) => {
+                self.declare(&f.name, "Function", f.span, Some(f.params.len()));
+                self.function(&f.params, &f.body);
+            },
+            DeclType::ClassDecl(class) => self.class(class),
+            DeclType::Import(_, name) => self.declare(name, "Module", decl.span, None),
+            DeclType::Stmt(stmt) => self.stmt(stmt),
+        }
+    }
+    fn class(&mut self, class: &Class)
+    {
+        self.declare(&class.name, "Class", class.span, None);
+        if let Some(s) = &class.superclass {
+            self.expr(s);
+        }
+        for method in &class.methods {
+            self.function(&method.params, &method.body);
+        }
+    }
+    fn function(&mut self, params: &[Parameter], body: &[Decl])
+    {
+        let enclosing = self.boundary;
+        self.scopes.push(HashMap::new());
+        self.boundary = self.scopes.len() - 1;
+        for param in params {
+            self.declare(&param.name, "Parameter", param.span, None);
+        }
+        self.decls(body);
+        self.endScope();
+        self.boundary = enclosing;
+    }
+    fn stmt(&mut self, stmt: &Stmt)
+    {
+        match &stmt.stmtType {
+            StmtType::ExprStmt(e) | StmtType::PrintStmt(e) | StmtType::ReturnStmt(Some(e)) | StmtType::ThrowStmt(e) => self.expr(e),
+            StmtType::IfStmt(c, i, e) => {
+                if let Some(value) = constant(c) {
+                    let truthy = !matches!(value, Literal::Nil | Literal::Bool(false));
+                    self.warn(LoxStaticError::warning("constant", c.span, format!("Condition is always {}", truthy).as_str()));
+                }
+                self.expr(c);
+                self.stmt(i);
+                if let Some(e) = e {
+                    self.stmt(e);
+                }
+            },
+            StmtType::WhileStmt(c, b) => {
+                self.expr(c);
+                self.stmt(b);
+            },
+            StmtType::ForStmt(f) => {
+                self.scopes.push(HashMap::new());
+                if let Some(i) = &f.initialiser {
+                    self.decl(i);
+                }
+                if let Some(c) = &f.condition {
+                    self.expr(c);
+                }
+                self.stmt(&f.body);
+                if let Some(i) = &f.increment {
+                    self.expr(i);
+                }
+                self.endScope();
+            },
+            StmtType::TryStmt(b, c, f) => {
+                self.block(b);
+                if let Some((name, c)) = c {
+                    self.scopes.push(HashMap::new());
+                    self.declare(&name.name, "Variable", name.span, None);
+                    self.decls(c);
+                    self.endScope();
+                }
+                if let Some(f) = f {
+                    self.block(f);
+                }
+            },
+            StmtType::Block(b) => self.block(b),
+            StmtType::ReturnStmt(None) | StmtType::BreakStmt | StmtType::ContinueStmt => (),
+        }
+    }
+    fn block(&mut self, decls: &[Decl])
+    {
+        self.scopes.push(HashMap::new());
+        self.decls(decls);
+        self.endScope();
+    }
+    fn expr(&mut self, expr: &Expr)
+    {
+        let span = expr.span;
+        match &expr.exprType {
+            ExprType::Variable(v) => {
+                if let Some(binding) = self.lookup(&v.name) {
+                    binding.used = true;
+                }
+            },
+            ExprType::Assignment(a) => {
+                self.expr(&a.expr);
+                match self.lookup(&a.id) {
+                    //It may no longer hold the function it was declared as
+                    Some(binding) => binding.arity = None,
+                    None => self.warn(LoxStaticError::warning("undeclared", span, format!("Assignment to undeclared variable {}", a.id).as_str())),
+                }
+            },
+            ExprType::Call(c) => {
+                self.expr(&c.callee);
+                for arg in &c.arguments {
+                    self.expr(arg);
+                }
+                if let ExprType::Variable(v) = &c.callee.exprType {
+                    let known = self.lookup(&v.name).and_then(|b| b.arity.map(|a| (a, b.span)));
+                    if let Some((arity, declared)) = known.filter(|(a, _)| *a != c.arguments.len()) {
+                        let message = format!("Function {} expects {} arguments but is given {}", v.name, arity, c.arguments.len());
+                        let mut warning = LoxStaticError::warning("arity", span, &message);
+                        if let Some(declared) = declared {
+                            warning = warning.withNote(declared, "function declared here");
+                        }
+                        self.warn(warning);
+                    }
+                }
+            },
+            ExprType::Lambda(l) => self.function(&l.params, &l.body),
+            ExprType::Interpolation(i) => {
+                for part in &i.parts {
+                    if let Part::Value(value) = part {
+                        self.expr(value);
+                    }
+                }
+            },
+            ExprType::Unary(u) => self.expr(&u.expr),
+            ExprType::Grouping(g) => self.expr(&g.expr),
+            ExprType::Logical(Logical{ lexpr, rexpr, .. }) | ExprType::Binary(Binary{ lexpr, rexpr, .. }) => {
+                self.expr(lexpr);
+                self.expr(rexpr);
+            },
+            ExprType::Get(g) => self.expr(&g.object),
+            ExprType::Set(s) => {
+                self.expr(&s.value);
+                self.expr(&s.object);
+            },
+            ExprType::List(l) => {
+                for element in &l.elements {
+                    self.expr(element);
+                }
+            },
+            ExprType::Map(m) => {
+                for (k, v) in &m.entries {
+                    self.expr(k);
+                    self.expr(v);
+                }
+            },
+            ExprType::Index(i) => {
+                self.expr(&i.object);
+                self.expr(&i.index);
+            },
+            ExprType::IndexSet(i) => {
+                self.expr(&i.object);
+                self.expr(&i.index);
+                self.expr(&i.value);
+            },
+            ExprType::Literal(_) | ExprType::This(_) | ExprType::Super(_) => (),
+        }
+    }
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding>
+    {
+        let boundary = self.boundary;
+        self.scopes.iter_mut().enumerate().rev()
+            .find_map(|(i, s)| s.get_mut(name).filter(|b| !b.pending || i < boundary))
+    }
+    //Globals were all declared up front. Names starting with "_" are never reported as unused
+    fn declare(&mut self, name: &str, kind: &'static str, span: Span, arity: Option<usize>)
+    {
+        if self.scopes.len() == 1 {
+            return;
+        }
+        let (scope, enclosing) = self.scopes.split_last_mut().expect("There is always a global scope");
+        //Redeclaring in the same scope is an error the resolver reports
+        let used = match scope.get(name) {
+            Some(b) if b.pending => b.used,
+            Some(_) => return,
+            None => false,
+        };
+        let outer = enclosing.iter().rev().find_map(|s| s.get(name)).map(|o| (o.kind, o.span));
+        scope.insert(name.to_string(), Binding{ kind, span: Some(span), used: used || name.starts_with('_'), arity, pending: false });
+        if let Some((outerKind, outerSpan)) = outer {
+            let mut warning = LoxStaticError::warning("shadow", span, format!("{} {} shadows an outer declaration", kind, name).as_str());
+            if let Some(outerSpan) = outerSpan {
+                warning = warning.withNote(outerSpan, format!("{} {} declared here", outerKind.to_lowercase(), name).as_str());
+            }
+            self.warn(warning);
+        }
+    }
+    fn endScope(&mut self)
+    {
+        let scope = self.scopes.pop().unwrap_or_default();
+        for (name, binding) in scope {
+            if let (false, Some(span)) = (binding.used, binding.span) {
+                self.warn(LoxStaticError::warning("unused", span, format!("{} {} is never used", binding.kind, name).as_str()));
+            }
+        }
+    }
+}
+
+//The global a top-level declaration defines, with its kind, span and arity if it is a function
+fn declared(decl: &Decl) -> Option<(&str, &'static str, Span, Option<usize>)>
+{
+    match &decl.declType {
+        DeclType::VarDecl(name, _) => Some((name, "Variable", decl.span, None)),
+        DeclType::FunDecl(f) => Some((&f.name, "Function", f.span, Some(f.params.len()))),
+        DeclType::ClassDecl(c) => Some((&c.name, "Class", c.span, None)),
+        DeclType::Import(_, name) => Some((name, "Module", decl.span, None)),
+        DeclType::Stmt(_) => None,
+    }
+}
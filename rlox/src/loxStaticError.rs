@@ -1,18 +1,46 @@
+use crate::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity
+{
+    //Stops the script from running
+    Error,
+    //Found by a lint, named so it can be allowed. The script still runs
+    Warning(&'static str),
+}
+
+#[derive(Debug)]
 pub struct LoxStaticError
 {
-    line: u32,
-    message: String,
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+    //Related places in the source, each with an explanation
+    pub notes: Vec<(Span, String)>,
 }
 impl LoxStaticError
 {
-    pub fn new(line: u32, message: &str) -> LoxStaticError
+    pub fn new(span: Span, message: &str) -> LoxStaticError
+    {
+        LoxStaticError{ severity: Severity::Error, span, message: message.to_string(), notes: Vec::new() }
+    }
+    pub fn warning(lint: &'static str, span: Span, message: &str) -> LoxStaticError
+    {
+        LoxStaticError{ severity: Severity::Warning(lint), ..LoxStaticError::new(span, message) }
+    }
+    pub fn withNote(mut self, span: Span, note: &str) -> LoxStaticError
     {
-        LoxStaticError{ line, message: message.to_string() }
+        self.notes.push((span, note.to_string()));
+        self
     }
 }
 impl std::fmt::Display for LoxStaticError
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Error on line {}: {}", self.line, self.message)
+        let label = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning(_) => "Warning",
+        };
+        write!(f, "{} on line {}, column {}: {}", label, self.span.line, self.span.column, self.message)
     }
 }
@@ -1,108 +1,429 @@
-#![allow(unused_must_use, non_snake_case, non_camel_case_types)]
-use std::path::Path;
-use std::fs::File;
-use std::io::{Write, Read};
+#![allow(non_snake_case)]
+use std::path::PathBuf;
+use rustyline::Editor;
+use rustyline::error::ReadlineError;
+use rlox::{Interpreter, LoxError, LoxValue};
+use rlox::diagnostic::{Diagnostic, Renderer, ColorChoice};
+use rlox::format::{format, Style};
+use rlox::lint::LINTS;
+use rlox::scan::scan;
+use rlox::token::TokenType;
 
-mod token;
+//The exit codes from sysexits.h that clox uses
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_NOINPUT: i32 = 66;
+const EX_SOFTWARE: i32 = 70;
+const EX_CANTCREAT: i32 = 73;
 
-mod scan;
-use crate::scan::scan;
-
-mod loxStaticError;
-use loxStaticError::LoxStaticError;
-
-mod expr;
+#[derive(Clone, Copy, PartialEq)]
+enum Dump
+{
+    Text,
+    Json,
+}
+impl TryFrom<&str> for Dump
+{
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, Self::Error>
+    {
+        match value {
+            "text" => Ok(Dump::Text),
+            "json" => Ok(Dump::Json),
+            x => Err(format!("Invalid dump format {}, expected text or json", x)),
+        }
+    }
+}
 
-mod parse;
-use parse::Parser;
+struct Options
+{
+    renderer: Renderer,
+    //Print the tokens of each source before running it
+    tokens: Option<Dump>,
+    //Print the parsed declarations of each source before running it
+    ast: Option<Dump>,
+    //Stop after reporting static errors, without running anything
+    check: bool,
+}
 
-mod interpret;
-use interpret::execute;
-use interpret::env::Env;
+fn exitCode(error: &LoxError) -> i32
+{
+    match error {
+        LoxError::Static(_) => EX_DATAERR,
+        LoxError::Runtime(_) => EX_SOFTWARE,
+        LoxError::Io(..) => EX_NOINPUT,
+        LoxError::Exit(code) => *code,
+        LoxError::Json(_) => EX_DATAERR,
+    }
+}
 
-fn run(source: String, env: Env) -> Result<Env, ()>
+//name is only used to refer to the source in diagnostics. Errors are reported before being returned
+#[allow(clippy::result_large_err)]
+fn run(interpreter: &mut Interpreter, source: &str, name: &str, options: &Options) -> Result<LoxValue, LoxError>
 {
-    fn detectStaticErrors(errors: &Vec<LoxStaticError>) -> Result<(), ()> {
-        if !errors.is_empty() {
-            for error in errors {
-                println!("{}", error);
+    match options.tokens {
+        Some(Dump::Text) => {
+            let (tokens, _) = scan(source.to_string(), 0);
+            for token in &tokens {
+                println!("{}:{} {:?}", token.span.line, token.span.column, token.tokenType);
             }
-            return Err(());
+        },
+        Some(Dump::Json) => {
+            let (tokens, _) = scan(source.to_string(), 0);
+            println!("{}", serde_json::to_string_pretty(&tokens).expect("Tokens can always be serialized"));
+        },
+        None => (),
+    }
+    let result = interpreter.compile(source, name).and_then(|program| {
+        match options.ast {
+            Some(Dump::Text) => {
+                for decl in &program {
+                    println!("{}", decl);
+                }
+            },
+            Some(Dump::Json) => println!("{}", serde_json::to_string_pretty(&program).expect("Trees can always be serialized")),
+            None => (),
         }
-        Ok(())
+        match options.check {
+            true => Ok(LoxValue::Nil),
+            false => interpreter.execute(program),
+        }
+    });
+    match result {
+        Ok(value) => Ok(value),
+        //Exiting is not a failure, so there is nothing to report
+        Err(e @ LoxError::Exit(_)) => Err(e),
+        Err(e) => {
+            eprintln!("{}", interpreter.render(&e, &options.renderer));
+            Err(e)
+        },
     }
+}
 
-    let (tokens, errors) = scan(source);
-    detectStaticErrors(&errors)?;
+#[allow(clippy::result_large_err)]
+fn run_file(filename: &str, args: Vec<String>, options: &Options) -> Result<(), LoxError>
+{
+    match std::fs::read_to_string(filename) {
+        Ok(content) => {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_args(args);
+            interpreter.enter(filename);
+            run(&mut interpreter, &content, filename, options).map(|_| ())
+        }
+        Err(e) => {
+            let error = LoxError::Io(filename.to_string(), e);
+            eprintln!("{}", error);
+            Err(error)
+        }
+    }
+}
 
-    //for token in &tokens {
-    //    println!("{:?}", token);
-    //}
+//How many more brackets the source opens than it closes
+fn depth(source: &str) -> i32
+{
+    let (tokens, _) = scan(source.to_string(), 0);
+    tokens.iter().map(|t| match t.tokenType {
+        TokenType::LEFT_PAREN | TokenType::LEFT_BRACE | TokenType::LEFT_BRACKET => 1,
+        TokenType::RIGHT_PAREN | TokenType::RIGHT_BRACE | TokenType::RIGHT_BRACKET => -1,
+        _ => 0,
+    }).sum()
+}
 
-    let parser = Parser::new(tokens);
-    let (program, errors) = parser.parse();
-    for decl in &program {
-        println!("{}", decl);
-    }
-    detectStaticErrors(&errors)?;
+fn historyPath() -> Option<PathBuf>
+{
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".rlox_history"))
+}
 
-    let result = execute(program, env, &mut None);
-    match result {
-        Ok(e) => Ok(e),
-        Err(e) => { println!("{}", e); Err(())},
+//Runs a line starting with ":" in the prompt
+#[allow(clippy::result_large_err)]
+fn command(interpreter: &mut Interpreter, line: &str, options: &Options) -> Result<(), LoxError>
+{
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    match name {
+        "env" => {
+            for (name, value) in interpreter.globals() {
+                if !matches!(value, LoxValue::Native(_)) {
+                    println!("{} = {}", name, value);
+                }
+            }
+        },
+        "load" => match std::fs::read_to_string(rest) {
+            Ok(content) => { let _ = run(interpreter, &content, rest, options); },
+            Err(e) => eprintln!("{}", LoxError::Io(rest.to_string(), e)),
+        },
+        "reset" => *interpreter = Interpreter::new(),
+        "ast" => match interpreter.compile(rest, "<stdin>") {
+            Ok(program) => {
+                for decl in &program {
+                    println!("{}", decl);
+                }
+            },
+            Err(e) => eprintln!("{}", interpreter.render(&e, &options.renderer)),
+        },
+        "quit" => return Err(LoxError::Exit(0)),
+        "help" => {
+            println!(":env          list the global variables");
+            println!(":load <file>  run a file, keeping its globals");
+            println!(":reset        forget every global variable");
+            println!(":ast <code>   show how code is parsed without running it");
+            println!(":quit         leave the prompt");
+        },
+        _ => eprintln!("Unknown command :{}, type :help for a list", name),
     }
+    Ok(())
 }
 
+//Reads code until its brackets are balanced, echoing the value of a trailing expression.
+//Returns once input ends, or with Exit if the code calls exit
+#[allow(clippy::result_large_err)]
+fn run_prompt(options: &Options) -> Result<(), LoxError>
+{
+    let mut editor = match Editor::<()>::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("Could not start the prompt: {}", e);
+            return Err(LoxError::Exit(EX_SOFTWARE));
+        },
+    };
+    let history = historyPath();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path);
+    }
+    let mut interpreter = Interpreter::new();
+    let mut input = String::new();
+    let result = loop {
+        let prompt = if input.is_empty() { "> " } else { "... " };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                input += &line;
+                input.push('\n');
+            },
+            //Ctrl-C abandons the code entered so far
+            Err(ReadlineError::Interrupted) => {
+                input.clear();
+                continue;
+            },
+            Err(_) => break Ok(()),
+        }
+        if depth(&input) > 0 { continue; }
+        let entry = std::mem::take(&mut input);
+        let entry = entry.trim();
+        if entry.is_empty() { continue; }
+        editor.add_history_entry(entry);
+        let result = match entry.strip_prefix(':') {
+            Some(line) => command(&mut interpreter, line, options),
+            None => match run(&mut interpreter, entry, "<stdin>", options) {
+                Ok(LoxValue::Nil) => Ok(()),
+                Ok(value) => {
+                    println!("{}", value);
+                    Ok(())
+                },
+                Err(e) => Err(e),
+            },
+        };
+        if let Err(e @ LoxError::Exit(_)) = result {
+            break Err(e);
+        }
+    };
+    if let Some(path) = &history {
+        let _ = editor.save_history(path);
+    }
+    result
+}
 
+fn usage() -> !
+{
+    eprintln!("Usage: rlox [--color=auto|always|never] [--tokens] [--ast] [--dump-tokens=text|json] [--dump-ast=text|json]");
+    eprintln!("            [--check] [-e code | script] [--] [args...]");
+    eprintln!("       rlox fmt [--check] [--indent=N] [--width=N] files...");
+    eprintln!("       rlox lint [--color=auto|always|never] files...");
+    eprintln!("Lints, allowed on a line by \"// lint: allow(name)\": {}", LINTS.join(", "));
+    std::process::exit(EX_USAGE);
+}
 
-fn run_file(filename: impl AsRef<Path>)
+//"rlox fmt" rewrites each file in the canonical style. With --check it only lists the
+//files that are not, failing if there are any. Returns the exit code
+fn fmt(arguments: impl Iterator<Item = String>) -> i32
 {
-    match File::open(filename) {
-        Ok(mut file) => {
-            let mut content = String::new();
-            file.read_to_string(&mut content).expect("Error reading file to string");
-            run(content, Env::global());
+    let mut style = Style::default();
+    let mut check = false;
+    let mut color = ColorChoice::Auto;
+    let mut files = Vec::new();
+    let number = |n: &str| n.parse::<usize>().unwrap_or_else(|_| usage());
+    for arg in arguments {
+        if arg == "--check" {
+            check = true;
         }
-        Err(e) => {
-            println!("An error occured opening file: {}", e);
-            std::process::exit(66);
+        else if let Some(n) = arg.strip_prefix("--indent=") {
+            style.indent = number(n);
+        }
+        else if let Some(n) = arg.strip_prefix("--width=") {
+            style.width = number(n);
+        }
+        else if let Some(choice) = arg.strip_prefix("--color=") {
+            color = ColorChoice::try_from(choice).unwrap_or_else(|_| usage());
+        }
+        else if arg.starts_with('-') {
+            usage();
+        }
+        else {
+            files.push(arg);
         }
     }
+    if files.is_empty() { usage(); }
+    let renderer = Renderer::new(color);
+    let mut code = 0;
+    for file in files {
+        let source = match std::fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}", LoxError::Io(file, e));
+                code = EX_NOINPUT;
+                continue;
+            },
+        };
+        match format(&source, style) {
+            Ok(formatted) if formatted == source => (),
+            Ok(_) if check => {
+                println!("{}", file);
+                code = code.max(1);
+            },
+            Ok(formatted) => {
+                if let Err(e) = std::fs::write(&file, formatted) {
+                    eprintln!("Cannot write {}: {}", file, e);
+                    code = EX_CANTCREAT;
+                }
+            },
+            Err(errors) => {
+                let sources = [(file, source)];
+                for e in &errors {
+                    eprintln!("{}", renderer.render(&Diagnostic::from(e), &sources));
+                }
+                code = EX_DATAERR;
+            },
+        }
+    }
+    code
 }
 
-fn run_prompt()
+//"rlox lint" reports the warnings in each file without running it, failing if there are any.
+//Returns the exit code
+fn lint(arguments: impl Iterator<Item = String>) -> i32
 {
-    let mut env = Env::global();
-    loop {
-        let mut input = String::new();
-        print!(">");
-        std::io::stdout().flush().expect("Output flush error");
-        std::io::stdin().read_line(&mut input).expect("Did not enter a valid string");
-
-        //removes new line
-        input.pop();
-        if input.is_empty() { break; }
-        let archive = env.clone();
-        match run(input, env) {
-            Ok(e) => env = e,
-            Err(_) => env = archive,
+    let mut color = ColorChoice::Auto;
+    let mut files = Vec::new();
+    for arg in arguments {
+        if let Some(choice) = arg.strip_prefix("--color=") {
+            color = ColorChoice::try_from(choice).unwrap_or_else(|_| usage());
+        }
+        else if arg.starts_with('-') {
+            usage();
+        }
+        else {
+            files.push(arg);
         }
     }
+    if files.is_empty() { usage(); }
+    let renderer = Renderer::new(color);
+    let mut code = 0;
+    for file in files {
+        let source = match std::fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}", LoxError::Io(file, e));
+                code = EX_NOINPUT;
+                continue;
+            },
+        };
+        let mut interpreter = Interpreter::new();
+        interpreter.enter(&file);
+        match interpreter.lint(&source, &file) {
+            Ok(warnings) if warnings.is_empty() => (),
+            Ok(warnings) => {
+                eprintln!("{}", interpreter.render(&LoxError::Static(warnings), &renderer));
+                code = code.max(1);
+            },
+            Err(e) => {
+                eprintln!("{}", interpreter.render(&e, &renderer));
+                code = EX_DATAERR;
+            },
+        }
+    }
+    code
 }
 
 fn main()
 {
-    let mut args = std::env::args();
-    match args.len() {
-        x if x > 2 => {
-            println!("Usage: rlox [script]");
-            std::process::exit(64);
+    match std::env::args().nth(1).as_deref() {
+        Some("fmt") => std::process::exit(fmt(std::env::args().skip(2))),
+        Some("lint") => std::process::exit(lint(std::env::args().skip(2))),
+        _ => (),
+    }
+    let mut color = ColorChoice::Auto;
+    let (mut tokens, mut ast, mut check) = (None, None, false);
+    let mut code = None;
+    //The script followed by its arguments. Options are not read after the script or "--"
+    let mut positional: Vec<String> = Vec::new();
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        if arg == "--" {
+            positional.extend(argv.by_ref());
+        }
+        else if !positional.is_empty() || !arg.starts_with('-') {
+            positional.push(arg);
         }
-        2 => {
-            run_file(args.nth(1).unwrap());
+        else if arg == "--tokens" {
+            tokens = Some(Dump::Text);
         }
-        _ => {
-            run_prompt();
+        else if arg == "--ast" {
+            ast = Some(Dump::Text);
         }
+        else if let Some(format) = arg.strip_prefix("--dump-tokens=") {
+            tokens = Some(Dump::try_from(format).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                usage()
+            }));
+        }
+        else if let Some(format) = arg.strip_prefix("--dump-ast=") {
+            ast = Some(Dump::try_from(format).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                usage()
+            }));
+        }
+        else if arg == "--check" {
+            check = true;
+        }
+        else if arg == "-e" {
+            code = Some(argv.next().unwrap_or_else(|| usage()));
+        }
+        else if let Some(choice) = arg.strip_prefix("--color=") {
+            color = ColorChoice::try_from(choice).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                usage()
+            });
+        }
+        else {
+            usage();
+        }
+    }
+    //JSON is read by tools, so the script does not run and mix its own output in
+    check |= tokens == Some(Dump::Json) || ast == Some(Dump::Json);
+    let options = Options{ renderer: Renderer::new(color), tokens, ast, check };
+    let result = match code {
+        //Inline code has no script, so every positional argument is passed to it
+        Some(c) => {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_args(positional);
+            run(&mut interpreter, &c, "<eval>", &options).map(|_| ())
+        },
+        None if positional.is_empty() => run_prompt(&options),
+        None => {
+            let script = positional.remove(0);
+            run_file(&script, positional, &options)
+        },
+    };
+    if let Err(e) = result {
+        std::process::exit(exitCode(&e));
     }
 }
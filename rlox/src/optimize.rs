@@ -0,0 +1,222 @@
+use crate::expr::*;
+use crate::expr::stmt::{Stmt, StmtType, Decl, DeclType, Function, Class, For};
+
+//Replaces operations on literals with the literal they produce, and removes branches and loops
+//that can never run. Operations that fail are left for the interpreter, so their errors are
+//reported exactly as before. Runs after the resolver, as it never adds or removes a scope
+pub fn optimize(program: Vec<Decl>) -> Vec<Decl>
+{
+    Optimizer.decls(program)
+}
+
+//The literal an expression always evaluates to, if it can be worked out without running it
+pub fn constant(expr: &Expr) -> Option<Literal>
+{
+    match &expr.exprType {
+        ExprType::Literal(l) => Some(l.clone()),
+        ExprType::Grouping(g) => constant(&g.expr),
+        ExprType::Unary(u) => unary(&u.operator, &constant(&u.expr)?),
+        ExprType::Binary(b) => binary(&constant(&b.lexpr)?, &b.operator, &constant(&b.rexpr)?),
+        ExprType::Logical(l) => {
+            let left = constant(&l.lexpr)?;
+            match truthy(&left) == matches!(l.operator, Loperator::Or) {
+                true => Some(left),
+                false => constant(&l.rexpr),
+            }
+        },
+        _ => None,
+    }
+}
+
+fn literal(expr: &Expr) -> Option<&Literal>
+{
+    match &expr.exprType {
+        ExprType::Literal(l) => Some(l),
+        _ => None,
+    }
+}
+
+fn truthy(literal: &Literal) -> bool
+{
+    !matches!(literal, Literal::Nil | Literal::Bool(false))
+}
+
+//The passes over each kind of node. They need no state, as folding never looks a name up
+struct Optimizer;
+
+impl Optimizer
+{
+    fn decls(&self, decls: Vec<Decl>) -> Vec<Decl>
+    {
+        decls.into_iter().filter_map(|d| self.decl(d)).collect()
+    }
+
+    fn decl(&self, decl: Decl) -> Option<Decl>
+    {
+        let declType = match decl.declType {
+            DeclType::FunDecl(function) => DeclType::FunDecl(self.function(function)),
+            DeclType::ClassDecl(class) => DeclType::ClassDecl(Class{
+                superclass: class.superclass.map(|s| self.expr(s)),
+                methods: class.methods.into_iter().map(|m| self.function(m)).collect(),
+                ..class
+            }),
+            DeclType::VarDecl(name, value) => DeclType::VarDecl(name, value.map(|v| self.expr(v))),
+            declType @ DeclType::Import(..) => declType,
+            DeclType::Stmt(stmt) => DeclType::Stmt(self.stmt(stmt)?),
+        };
+        Some(Decl::new(declType, decl.span))
+    }
+
+    fn function(&self, function: Function) -> Function
+    {
+        Function{ body: self.decls(function.body), ..function }
+    }
+
+    //None if the statement can never run
+    fn stmt(&self, stmt: Stmt) -> Option<Stmt>
+    {
+        let stmtType = match stmt.stmtType {
+            StmtType::ExprStmt(e) => StmtType::ExprStmt(self.expr(e)),
+            StmtType::PrintStmt(e) => StmtType::PrintStmt(self.expr(e)),
+            StmtType::ReturnStmt(e) => StmtType::ReturnStmt(e.map(|e| self.expr(e))),
+            StmtType::ThrowStmt(e) => StmtType::ThrowStmt(self.expr(e)),
+            StmtType::IfStmt(condition, then, otherwise) => {
+                let condition = self.expr(condition);
+                let otherwise = otherwise.and_then(|o| self.stmt(*o));
+                match literal(&condition) {
+                    Some(l) if truthy(l) => return self.stmt(*then),
+                    Some(_) => return otherwise,
+                    None => StmtType::IfStmt(condition, Box::new(self.branch(*then)), otherwise.map(Box::new)),
+                }
+            },
+            StmtType::WhileStmt(condition, body) => {
+                let condition = self.expr(condition);
+                if literal(&condition).is_some_and(|l| !truthy(l)) {
+                    return None;
+                }
+                StmtType::WhileStmt(condition, Box::new(self.branch(*body)))
+            },
+            StmtType::ForStmt(l) => {
+                let initialiser = l.initialiser.and_then(|i| self.decl(*i));
+                let condition = l.condition.map(|c| self.expr(c));
+                //The initialiser still runs, in a scope of its own as the loop would have had
+                if condition.as_ref().and_then(literal).is_some_and(|l| !truthy(l)) {
+                    return initialiser.map(|i| Stmt::new(StmtType::Block(vec![i]), stmt.span));
+                }
+                StmtType::ForStmt(For{
+                    initialiser: initialiser.map(Box::new),
+                    condition,
+                    increment: l.increment.map(|i| self.expr(i)),
+                    body: Box::new(self.branch(*l.body)),
+                })
+            },
+            StmtType::TryStmt(body, catch, finally) => StmtType::TryStmt(
+                self.decls(body),
+                catch.map(|(name, c)| (name, self.decls(c))),
+                finally.map(|f| self.decls(f)),
+            ),
+            StmtType::Block(decls) => StmtType::Block(self.decls(decls)),
+            stmtType @ (StmtType::BreakStmt | StmtType::ContinueStmt) => stmtType,
+        };
+        Some(Stmt::new(stmtType, stmt.span))
+    }
+
+    //A statement that has to stay, like the body of a loop, is left empty if it can never run
+    fn branch(&self, stmt: Stmt) -> Stmt
+    {
+        let span = stmt.span;
+        self.stmt(stmt).unwrap_or_else(|| Stmt::new(StmtType::Block(Vec::new()), span))
+    }
+
+    fn exprs(&self, exprs: Vec<Expr>) -> Vec<Expr>
+    {
+        exprs.into_iter().map(|e| self.expr(e)).collect()
+    }
+
+    fn boxed(&self, expr: Expr) -> Box<Expr>
+    {
+        Box::new(self.expr(expr))
+    }
+
+    fn expr(&self, expr: Expr) -> Expr
+    {
+        let exprType = match expr.exprType {
+            ExprType::Binary(b) => ExprType::Binary(Binary{ lexpr: self.boxed(*b.lexpr), operator: b.operator, rexpr: self.boxed(*b.rexpr) }),
+            ExprType::Unary(u) => ExprType::Unary(Unary{ operator: u.operator, expr: self.boxed(*u.expr) }),
+            ExprType::Grouping(g) => ExprType::Grouping(Grouping{ expr: self.boxed(*g.expr) }),
+            ExprType::Logical(l) => {
+                let (lexpr, rexpr) = (self.expr(*l.lexpr), self.expr(*l.rexpr));
+                //"and" gives its left operand if that is falsey, and "or" if it is truthy
+                match literal(&lexpr) {
+                    Some(v) if truthy(v) == matches!(l.operator, Loperator::Or) => return Expr::new(lexpr.exprType, expr.span),
+                    Some(_) => return rexpr,
+                    None => ExprType::Logical(Logical{ lexpr: Box::new(lexpr), operator: l.operator, rexpr: Box::new(rexpr) }),
+                }
+            },
+            ExprType::Assignment(a) => ExprType::Assignment(Assignment{ expr: self.boxed(*a.expr), ..a }),
+            ExprType::Call(c) => ExprType::Call(Call{ callee: self.boxed(*c.callee), arguments: self.exprs(c.arguments) }),
+            ExprType::Get(g) => ExprType::Get(Get{ object: self.boxed(*g.object), ..g }),
+            ExprType::Set(s) => ExprType::Set(Set{ object: self.boxed(*s.object), value: self.boxed(*s.value), ..s }),
+            ExprType::List(l) => ExprType::List(List{ elements: self.exprs(l.elements) }),
+            ExprType::Map(m) => ExprType::Map(Map{ entries: m.entries.into_iter().map(|(k, v)| (self.expr(k), self.expr(v))).collect() }),
+            ExprType::Index(i) => ExprType::Index(Index{ object: self.boxed(*i.object), index: self.boxed(*i.index) }),
+            ExprType::IndexSet(i) => ExprType::IndexSet(IndexSet{ object: self.boxed(*i.object), index: self.boxed(*i.index), value: self.boxed(*i.value) }),
+            ExprType::Lambda(l) => ExprType::Lambda(Lambda{ body: self.decls(l.body), ..l }),
+            ExprType::Interpolation(i) => ExprType::Interpolation(Interpolation{ parts: i.parts.into_iter().map(|p| match p {
+                Part::Value(v) => Part::Value(self.expr(v)),
+                text => text,
+            }).collect() }),
+            exprType @ (ExprType::Literal(_) | ExprType::Variable(_) | ExprType::This(_) | ExprType::Super(_)) => exprType,
+        };
+        fold(Expr::new(exprType, expr.span))
+    }
+}
+
+//Replaces an operation whose operands are all literals with its result, keeping it if that fails
+fn fold(expr: Expr) -> Expr
+{
+    let folded = match &expr.exprType {
+        ExprType::Binary(b) => literal(&b.lexpr).zip(literal(&b.rexpr)).and_then(|(l, r)| binary(l, &b.operator, r)),
+        ExprType::Unary(u) => literal(&u.expr).and_then(|l| unary(&u.operator, l)),
+        ExprType::Grouping(g) => literal(&g.expr).cloned(),
+        _ => None,
+    };
+    match folded {
+        Some(l) => Expr::new(ExprType::Literal(l), expr.span),
+        None => expr,
+    }
+}
+
+//The operators work as the interpreter's do, giving None where it would report an error
+
+fn unary(operator: &Uoperator, operand: &Literal) -> Option<Literal>
+{
+    match (operator, operand) {
+        (Uoperator::Minus, Literal::Num(n)) => Some(Literal::Num(-n)),
+        (Uoperator::Minus, _) => None,
+        (Uoperator::Bang, l) => Some(Literal::Bool(!truthy(l))),
+    }
+}
+
+fn binary(left: &Literal, operator: &Boperator, right: &Literal) -> Option<Literal>
+{
+    let numbers = match (left, right) {
+        (Literal::Num(l), Literal::Num(r)) => Some((*l, *r)),
+        _ => None,
+    };
+    match operator {
+        Boperator::EqualEqual => Some(Literal::Bool(left == right)),
+        Boperator::BangEqual => Some(Literal::Bool(left != right)),
+        Boperator::Plus => match (left, right) {
+            (Literal::Str(l), Literal::Str(r)) => Some(Literal::Str(format!("{}{}", l, r))),
+            _ => numbers.map(|(l, r)| Literal::Num(l + r)),
+        },
+        Boperator::Minus => numbers.map(|(l, r)| Literal::Num(l - r)),
+        Boperator::Star => numbers.map(|(l, r)| Literal::Num(l * r)),
+        Boperator::Slash => numbers.map(|(l, r)| Literal::Num(l / r)),
+        Boperator::Less => numbers.map(|(l, r)| Literal::Bool(l < r)),
+        Boperator::LessEqual => numbers.map(|(l, r)| Literal::Bool(l <= r)),
+        Boperator::Greater => numbers.map(|(l, r)| Literal::Bool(l > r)),
+        Boperator::GreaterEqual => numbers.map(|(l, r)| Literal::Bool(l >= r)),
+    }
+}
@@ -1,6 +1,7 @@
 use crate::token::*;
 use crate::expr::*;
-use crate::expr::stmt::{Stmt, Decl};
+use crate::expr::stmt::{Stmt, StmtType, Decl, DeclType, Function, Class, For};
+use crate::span::Span;
 use crate::LoxStaticError;
 
 pub struct Parser
@@ -8,12 +9,14 @@ pub struct Parser
     pub tokens: Vec<Token>,
     current: usize,
     errors: Vec<LoxStaticError>,
+    //Set after an error until the next declaration, so one mistake is only reported once
+    panicking: bool,
 }
 impl Parser
 {
     pub fn new(tokens: Vec<Token>) -> Self
     {
-        Parser{tokens, current: 0, errors: Vec::new()}
+        Parser{tokens, current: 0, errors: Vec::new(), panicking: false}
     }
     pub fn parse(mut self) -> (Vec<Decl>, Vec<LoxStaticError>)
     {
@@ -25,9 +28,29 @@ impl Parser
     }
     fn declaration(&mut self) -> Decl
     {
+        self.panicking = false;
+        let start = self.peek();
         if self.check(vec![TokenType::VAR]) { return self.varDeclaration(); }
-        if self.check(vec![TokenType::FUN]) { return self.funDeclaration("function"); }
-        Decl::Stmt(self.statement())
+        //"fun" followed by "(" starts a lambda, which is parsed as an expression statement
+        if self.tokens[self.current+1].tokenType != TokenType::LEFT_PAREN && self.check(vec![TokenType::FUN]) {
+            let declType = DeclType::FunDecl(self.funDeclaration("function"));
+            return Decl::new(declType, start.to(self.previous()));
+        }
+        if self.check(vec![TokenType::CLASS]) { return self.classDeclaration(); }
+        if self.check(vec![TokenType::IMPORT]) { return self.importDeclaration(); }
+        let stmt = self.statement();
+        let span = stmt.span;
+        Decl::new(DeclType::Stmt(stmt), span)
+    }
+    //The span of the token just consumed
+    fn previous(&self) -> Span
+    {
+        self.tokens[self.current-1].span
+    }
+    //The span of the next token to be consumed
+    fn peek(&self) -> Span
+    {
+        self.tokens[self.current].span
     }
     fn idConsume(&mut self, err: &str) -> String
     {
@@ -45,55 +68,139 @@ impl Parser
             String::new()
         }
     }
-    fn funDeclaration(&mut self, kind: &str) -> Decl
+    fn classDeclaration(&mut self) -> Decl
+    {
+        let start = self.previous();
+        let span = self.peek();
+        let name = self.idConsume("Expected class name");
+        let mut superclass = None;
+        if self.check(vec![TokenType::LESS]) {
+            let span = self.peek();
+            let name = self.idConsume("Expected superclass name");
+            superclass = Some(Expr::new(ExprType::Variable(Variable{name, depth: None}), span));
+        }
+        self.consume(TokenType::LEFT_BRACE, r#"Expected "{" before class body"#);
+        let mut methods = Vec::new();
+        while self.tokens[self.current].tokenType != TokenType::RIGHT_BRACE && !self.end() {
+            methods.push(self.funDeclaration("method"));
+        }
+        self.consume(TokenType::RIGHT_BRACE, r#"Expected "}" after class body"#);
+        Decl::new(DeclType::ClassDecl(Class{name, superclass, methods, span}), start.to(self.previous()))
+    }
+    fn funDeclaration(&mut self, kind: &str) -> Function
     {
+        let span = self.peek();
         let name = self.idConsume(format!("Expected {} name", kind).as_str());
         self.consume(TokenType::LEFT_PAREN, format!(r#"Expected "(" after {} name"#, kind).as_str());
+        let parameters = self.parameters();
+        self.consume(TokenType::LEFT_BRACE, format!(r#"Expected "{{" before {} body"#, kind).as_str());
+        let body = self.block();
+        Function{name, params: parameters, body, span}
+    }
+    //Parses a parameter list up to and including the closing ")"
+    fn parameters(&mut self) -> Vec<Parameter>
+    {
         let mut parameters = Vec::new();
         if self.tokens[self.current].tokenType != TokenType::RIGHT_PAREN {
             loop {
                 if parameters.len() >= 255 {
                     self.error("Can't have more than 255 parameters");
                 }
-                parameters.push(self.idConsume("Expected parameter name"));
+                let name = self.idConsume("Expected parameter name");
+                parameters.push(Parameter{name, span: self.previous()});
                 if !self.check(vec![TokenType::COMMA]) { break }
             }
         }
         self.consume(TokenType::RIGHT_PAREN, r#"Expected ")" after parameters"#);
-        self.consume(TokenType::LEFT_BRACE, format!(r#"Expected "{{" before {} body"#, kind).as_str());
-        let body = self.block();
-        Decl::FunDecl(name, parameters, body)
+        parameters
+    }
+    //Whether the "(" about to be consumed starts the parameters of an arrow function
+    fn isArrow(&self) -> bool
+    {
+        let mut i = self.current + 1;
+        if self.tokens[i].tokenType != TokenType::RIGHT_PAREN {
+            loop {
+                if !matches!(self.tokens[i].tokenType, TokenType::IDENTIFIER(_)) { return false }
+                i += 1;
+                match self.tokens[i].tokenType {
+                    TokenType::COMMA => i += 1,
+                    TokenType::RIGHT_PAREN => break,
+                    _ => return false,
+                }
+            }
+        }
+        self.tokens[i+1].tokenType == TokenType::ARROW
+    }
+    fn importDeclaration(&mut self) -> Decl
+    {
+        let start = self.previous();
+        let path = match self.tokens[self.current].tokenType.clone() {
+            TokenType::STRING(p) => { self.current += 1; p },
+            _ => { self.error(r#"Expected module path after "import""#); String::new() },
+        };
+        //"as" is only special here, so it can still be used as a name elsewhere
+        let name = if self.tokens[self.current].tokenType == TokenType::IDENTIFIER("as".to_string()) {
+            self.current += 1;
+            self.idConsume(r#"Expected module name after "as""#)
+        }
+        else {
+            let stem = std::path::Path::new(&path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let valid = stem.chars().enumerate().all(|(i, c)| c == '_' || c.is_alphabetic() || (i > 0 && c.is_ascii_digit()));
+            if stem.is_empty() || !valid {
+                self.error(r#"Module file name is not a valid name, use "as" to name it"#);
+            }
+            stem
+        };
+        self.consume(TokenType::SEMICOLON, r#"Expected ";" after import"#);
+        Decl::new(DeclType::Import(path, name), start.to(self.previous()))
     }
     fn varDeclaration(&mut self) -> Decl
     {
+        let start = self.previous();
         let name = self.idConsume("Expected variable name");
-        let mut value = Expr::new(ExprType::Literal(Literal::Nil), self.tokens[self.current].line);
+        let mut value = None;
         if self.check(vec![TokenType::EQUAL]) {
-            value = self.expression();
+            value = Some(self.expression());
         }
         self.consume(TokenType::SEMICOLON, r#"Expected ";" after variable declaration"#);
-        Decl::VarDecl(name, value)
+        Decl::new(DeclType::VarDecl(name, value), start.to(self.previous()))
     }
     fn statement(&mut self) -> Stmt
     {
-        if self.check(vec![TokenType::IF]) { self.ifStatement() }
+        let start = self.peek();
+        let stmtType = if self.check(vec![TokenType::IF]) { self.ifStatement() }
         else if self.check(vec![TokenType::PRINT]) { self.printStatement() }
-        else if self.check(vec![TokenType::LEFT_BRACE]) { Stmt::Block(self.block()) }
+        else if self.check(vec![TokenType::LEFT_BRACE]) { StmtType::Block(self.block()) }
         else if self.check(vec![TokenType::WHILE]) { self.whileStatement() }
         else if self.check(vec![TokenType::FOR]) { self.forStatement() }
         else if self.check(vec![TokenType::RETURN]) { self.returnStatement() }
-        else { self.expressionStatement() }
+        else if self.check(vec![TokenType::TRY]) { self.tryStatement() }
+        else if self.check(vec![TokenType::THROW]) {
+            let value = self.expression();
+            self.consume(TokenType::SEMICOLON, r#"Expected ";" after thrown value"#);
+            StmtType::ThrowStmt(value)
+        }
+        else if self.check(vec![TokenType::BREAK]) {
+            self.consume(TokenType::SEMICOLON, r#"Expected ";" after "break""#);
+            StmtType::BreakStmt
+        }
+        else if self.check(vec![TokenType::CONTINUE]) {
+            self.consume(TokenType::SEMICOLON, r#"Expected ";" after "continue""#);
+            StmtType::ContinueStmt
+        }
+        else { self.expressionStatement() };
+        Stmt::new(stmtType, start.to(self.previous()))
     }
-    fn returnStatement(&mut self) -> Stmt
+    fn returnStatement(&mut self) -> StmtType
     {
         if self.check(vec![TokenType::SEMICOLON]) {
-            return Stmt::ReturnStmt(Expr::new(ExprType::Literal(Literal::Nil), self.tokens[self.current-1].line));
+            return StmtType::ReturnStmt(None);
         }
         let expr = self.expression();
         self.consume(TokenType::SEMICOLON, r#"Expected ";" after return value"#);
-        Stmt::ReturnStmt(expr)
+        StmtType::ReturnStmt(Some(expr))
     }
-    fn forStatement(&mut self) -> Stmt
+    fn forStatement(&mut self) -> StmtType
     {
         self.consume(TokenType::LEFT_PAREN, r#"Expected "(" after "for""#);
         let initialiser;
@@ -101,13 +208,14 @@ impl Parser
             initialiser = None;
         }
         else if self.check(vec![TokenType::VAR]) {
-            initialiser = Some(self.varDeclaration());
+            initialiser = Some(Box::new(self.varDeclaration()));
         }
         else {
-            initialiser = Some(Decl::Stmt(self.expressionStatement()));
+            let initStart = self.peek();
+            let stmt = Stmt::new(self.expressionStatement(), initStart.to(self.previous()));
+            initialiser = Some(Box::new(Decl::new(DeclType::Stmt(stmt), initStart.to(self.previous()))));
         }
         let mut condition = None;
-        let condLine = self.tokens[self.current].line;
         if self.tokens[self.current].tokenType != TokenType::SEMICOLON {
             condition = Some(self.expression());
         }
@@ -117,34 +225,41 @@ impl Parser
             increment = Some(self.expression());
         }
         self.consume(TokenType::RIGHT_PAREN, r#"Expected ")" after for clauses"#);
-        let mut body = self.statement();
-        if let Some(i) = increment {
-            body = Stmt::Block(vec![
-                Decl::Stmt(body),
-                Decl::Stmt(Stmt::ExprStmt(i)),
-            ])
-        };
-        if condition.is_none() {
-            condition = Some(Expr::new(ExprType::Literal(Literal::Bool(true)), condLine));
-        };
-        body = Stmt::WhileStmt(condition.unwrap(), Box::new(body));
-        if let Some(i) = initialiser {
-            body = Stmt::Block(vec![
-                i,
-                Decl::Stmt(body),
-            ])
-        }
-        body
+        let body = Box::new(self.statement());
+        StmtType::ForStmt(For{initialiser, condition, increment, body})
     }
-    fn whileStatement(&mut self) -> Stmt
+    fn whileStatement(&mut self) -> StmtType
     {
         self.consume(TokenType::LEFT_PAREN, r#"Expected "(" after "while""#);
         let condition = self.expression();
         self.consume(TokenType::RIGHT_PAREN, r#"Expected ")" after while condition"#);
         let body = self.statement();
-        Stmt::WhileStmt(condition, Box::new(body))
+        StmtType::WhileStmt(condition, Box::new(body))
     }
-    fn ifStatement(&mut self) -> Stmt
+    fn tryStatement(&mut self) -> StmtType
+    {
+        self.consume(TokenType::LEFT_BRACE, r#"Expected "{" after "try""#);
+        let body = self.block();
+        let mut catch = None;
+        if self.check(vec![TokenType::CATCH]) {
+            self.consume(TokenType::LEFT_PAREN, r#"Expected "(" after "catch""#);
+            let name = self.idConsume("Expected error variable name");
+            let name = Parameter{name, span: self.previous()};
+            self.consume(TokenType::RIGHT_PAREN, r#"Expected ")" after error variable name"#);
+            self.consume(TokenType::LEFT_BRACE, r#"Expected "{" before catch body"#);
+            catch = Some((name, self.block()));
+        }
+        let mut finally = None;
+        if self.check(vec![TokenType::FINALLY]) {
+            self.consume(TokenType::LEFT_BRACE, r#"Expected "{" after "finally""#);
+            finally = Some(self.block());
+        }
+        if catch.is_none() && finally.is_none() {
+            self.error(r#"Expected "catch" or "finally" after try block"#);
+        }
+        StmtType::TryStmt(body, catch, finally)
+    }
+    fn ifStatement(&mut self) -> StmtType
     {
         self.consume(TokenType::LEFT_PAREN, r#"Expected "(" after "if""#);
         let condition = self.expression();
@@ -154,29 +269,28 @@ impl Parser
         if self.check(vec![TokenType::ELSE]) {
             elseBranch = Some(Box::new(self.statement()));
         }
-        Stmt::IfStmt(condition, Box::new(thenBranch), elseBranch)
+        StmtType::IfStmt(condition, Box::new(thenBranch), elseBranch)
     }
     fn block(&mut self) -> Vec<Decl>
     {
         let mut decls: Vec<Decl> = Vec::new();
-        while !self.check(vec![TokenType::RIGHT_BRACE]) && !self.end() {
+        while self.tokens[self.current].tokenType != TokenType::RIGHT_BRACE && !self.end() {
             decls.push(self.declaration());
         }
-        self.current -= 1;
         self.consume(TokenType::RIGHT_BRACE, r#"Expected "}" after block"#);
         decls
     }
-    fn printStatement(&mut self) -> Stmt
+    fn printStatement(&mut self) -> StmtType
     {
         let value = self.expression();
         self.consume(TokenType::SEMICOLON, r#"Expected ";" after value"#);
-        Stmt::PrintStmt(value)
+        StmtType::PrintStmt(value)
     }
-    fn expressionStatement(&mut self) -> Stmt
+    fn expressionStatement(&mut self) -> StmtType
     {
         let value = self.expression();
         self.consume(TokenType::SEMICOLON, r#"Expected ";" after value"#);
-        Stmt::ExprStmt(value)
+        StmtType::ExprStmt(value)
     }
     fn end(&self) -> bool
     {
@@ -200,12 +314,15 @@ impl Parser
     {
         let expr = self.logic_or();
         while self.check(vec![TokenType::EQUAL]) {
-            let line = self.tokens[self.current-1].line;
+            let equals = self.previous();
             let value = self.assignment();
-            if let ExprType::Variable(n) = expr.exprType {
-                return Expr::new(ExprType::Assignment(Assignment{id: n, expr: Box::new(value)}), line)
+            let span = expr.span.to(value.span);
+            match expr.exprType {
+                ExprType::Variable(v) => return Expr::new(ExprType::Assignment(Assignment{id: v.name, expr: Box::new(value), depth: None}), span),
+                ExprType::Get(g) => return Expr::new(ExprType::Set(Set{object: g.object, name: g.name, value: Box::new(value)}), span),
+                ExprType::Index(i) => return Expr::new(ExprType::IndexSet(IndexSet{object: i.object, index: i.index, value: Box::new(value)}), span),
+                _ => self.errors.push(LoxStaticError::new(equals, "Invalid assignment target")),
             }
-            self.errors.push(LoxStaticError::new(line, "Invalid assignment target"));
         }
         expr
     }
@@ -213,10 +330,10 @@ impl Parser
     {
         let mut expr = self.logic_and();
         while self.check(vec![TokenType::OR]) {
-            let line = self.tokens[self.current-1].line;
             let operator = Loperator::try_from(self.tokens[self.current-1].tokenType.clone()).unwrap();
-            let right = self.comparison();
-            expr = Expr::new(ExprType::Logical(Logical{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), line);
+            let right = self.logic_and();
+            let span = expr.span.to(right.span);
+            expr = Expr::new(ExprType::Logical(Logical{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), span);
         }
         expr
     }
@@ -224,10 +341,10 @@ impl Parser
     {
         let mut expr = self.equality();
         while self.check(vec![TokenType::AND]) {
-            let line = self.tokens[self.current-1].line;
             let operator = Loperator::try_from(self.tokens[self.current-1].tokenType.clone()).unwrap();
-            let right = self.comparison();
-            expr = Expr::new(ExprType::Logical(Logical{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), line);
+            let right = self.equality();
+            let span = expr.span.to(right.span);
+            expr = Expr::new(ExprType::Logical(Logical{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), span);
         }
         expr
     }
@@ -235,10 +352,10 @@ impl Parser
     {
         let mut expr = self.comparison();
         while self.check(vec![TokenType::BANG_EQUAL, TokenType::EQUAL_EQUAL]) {
-            let line = self.tokens[self.current-1].line;
             let operator = Boperator::try_from(self.tokens[self.current-1].tokenType.clone()).unwrap();
             let right = self.comparison();
-            expr = Expr::new(ExprType::Binary(Binary{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), line);
+            let span = expr.span.to(right.span);
+            expr = Expr::new(ExprType::Binary(Binary{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), span);
         }
         expr
     }
@@ -246,39 +363,40 @@ impl Parser
     {
         let mut expr = self.term();
         while self.check(vec![TokenType::GREATER, TokenType::GREATER_EQUAL, TokenType::LESS, TokenType::LESS_EQUAL]) {
-            let line = self.tokens[self.current-1].line;
             let operator = Boperator::try_from(self.tokens[self.current-1].tokenType.clone()).unwrap();
             let right = self.term();
-            expr = Expr::new(ExprType::Binary(Binary{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), line);
+            let span = expr.span.to(right.span);
+            expr = Expr::new(ExprType::Binary(Binary{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), span);
         }
         expr
     }
     fn term(&mut self) -> Expr {
         let mut expr = self.factor();
         while self.check(vec![TokenType::MINUS, TokenType::PLUS]) {
-            let line = self.tokens[self.current-1].line;
             let operator = Boperator::try_from(self.tokens[self.current-1].tokenType.clone()).unwrap();
             let right = self.factor();
-            expr = Expr::new(ExprType::Binary(Binary{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), line);
+            let span = expr.span.to(right.span);
+            expr = Expr::new(ExprType::Binary(Binary{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), span);
         }
         expr
     }
     fn factor(&mut self) -> Expr {
         let mut expr = self.unary();
         while self.check(vec![TokenType::SLASH, TokenType::STAR]) {
-            let line = self.tokens[self.current-1].line;
             let operator = Boperator::try_from(self.tokens[self.current-1].tokenType.clone()).unwrap();
             let right = self.unary();
-            expr = Expr::new(ExprType::Binary(Binary{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), line);
+            let span = expr.span.to(right.span);
+            expr = Expr::new(ExprType::Binary(Binary{operator, lexpr: Box::new(expr), rexpr: Box::new(right)}), span);
         }
         expr
     }
     fn unary(&mut self) -> Expr {
         if self.check(vec![TokenType::BANG, TokenType::MINUS]) {
-            let line = self.tokens[self.current-1].line;
+            let start = self.previous();
             let operator = Uoperator::try_from(self.tokens[self.current-1].tokenType.clone()).unwrap();
             let right = self.unary();
-            return Expr::new(ExprType::Unary(Unary{operator, expr: Box::new(right)}), line);
+            let span = start.to(right.span);
+            return Expr::new(ExprType::Unary(Unary{operator, expr: Box::new(right)}), span);
         }
         self.call()
     }
@@ -288,6 +406,17 @@ impl Parser
             if self.check(vec![TokenType::LEFT_PAREN]) {
                 expr = self.finishCall(expr);
             }
+            else if self.check(vec![TokenType::DOT]) {
+                let name = self.idConsume(r#"Expected property name after ".""#);
+                let span = expr.span.to(self.previous());
+                expr = Expr::new(ExprType::Get(Get{object: Box::new(expr), name}), span);
+            }
+            else if self.check(vec![TokenType::LEFT_BRACKET]) {
+                let index = self.expression();
+                self.consume(TokenType::RIGHT_BRACKET, r#"Expected "]" after index"#);
+                let span = expr.span.to(self.previous());
+                expr = Expr::new(ExprType::Index(Index{object: Box::new(expr), index: Box::new(index)}), span);
+            }
             else { break }
         }
         expr
@@ -296,54 +425,135 @@ impl Parser
         let mut arguments = Vec::new();
         if self.tokens[self.current].tokenType != TokenType::RIGHT_PAREN {
             arguments.push(self.expression());
-            while self.tokens[self.current].tokenType == TokenType::COMMA {
+            while self.check(vec![TokenType::COMMA]) {
                 if arguments.len() >= 255 {
                     self.error("Can't have more than 255 arguments");
                 }
                 arguments.push(self.expression());
             }
         }
-        let line = self.tokens[self.current].line; 
         self.consume(TokenType::RIGHT_PAREN, r#"Expected ")" after argument"#);
-        Expr::new(ExprType::Call(Call{ callee: Box::new(callee), arguments }), line)
+        let span = callee.span.to(self.previous());
+        Expr::new(ExprType::Call(Call{ callee: Box::new(callee), arguments }), span)
     }
     fn primary(&mut self) -> Expr {
         let literal = Literal::try_from(self.tokens[self.current].tokenType.clone());
-        let line = self.tokens[self.current].line;
+        let start = self.peek();
         match literal {
             Ok(l) => {
                 if !self.end(){
                     self.current += 1;
                 }
-                return Expr::new(ExprType::Literal(l), line);
+                return Expr::new(ExprType::Literal(l), start);
             },
             Err(_) => {
+                if self.tokens[self.current].tokenType == TokenType::LEFT_PAREN && self.isArrow() {
+                    self.current += 1;
+                    let params = self.parameters();
+                    self.consume(TokenType::ARROW, r#"Expected "=>" after parameters"#);
+                    let value = self.expression();
+                    let span = value.span;
+                    let body = vec![Decl::new(DeclType::Stmt(Stmt::new(StmtType::ReturnStmt(Some(value)), span)), span)];
+                    return Expr::new(ExprType::Lambda(Lambda{params, body, arrow: true}), start.to(self.previous()));
+                }
+                if self.check(vec![TokenType::FUN]) {
+                    self.consume(TokenType::LEFT_PAREN, r#"Expected "(" after "fun""#);
+                    let params = self.parameters();
+                    self.consume(TokenType::LEFT_BRACE, r#"Expected "{" before function body"#);
+                    let body = self.block();
+                    return Expr::new(ExprType::Lambda(Lambda{params, body, arrow: false}), start.to(self.previous()));
+                }
                 if self.check(vec![TokenType::LEFT_PAREN]) {
                     let expr = self.expression();
                     self.consume(TokenType::RIGHT_PAREN, r#"Expected ")""#);
-                    return Expr::new(ExprType::Grouping(Grouping{expr: Box::new(expr)}), line);
+                    return Expr::new(ExprType::Grouping(Grouping{expr: Box::new(expr)}), start.to(self.previous()));
+                }
+                if let TokenType::INTERPOLATION(_) = self.tokens[self.current].tokenType {
+                    return self.interpolation();
+                }
+                if self.check(vec![TokenType::LEFT_BRACKET]) {
+                    let mut elements = Vec::new();
+                    if self.tokens[self.current].tokenType != TokenType::RIGHT_BRACKET {
+                        elements.push(self.expression());
+                        while self.check(vec![TokenType::COMMA]) {
+                            elements.push(self.expression());
+                        }
+                    }
+                    self.consume(TokenType::RIGHT_BRACKET, r#"Expected "]" after list elements"#);
+                    return Expr::new(ExprType::List(List{elements}), start.to(self.previous()));
+                }
+                if self.check(vec![TokenType::LEFT_BRACE]) {
+                    let mut entries = Vec::new();
+                    if self.tokens[self.current].tokenType != TokenType::RIGHT_BRACE {
+                        loop {
+                            let key = self.expression();
+                            self.consume(TokenType::COLON, r#"Expected ":" after map key"#);
+                            entries.push((key, self.expression()));
+                            if !self.check(vec![TokenType::COMMA]) { break }
+                        }
+                    }
+                    self.consume(TokenType::RIGHT_BRACE, r#"Expected "}" after map entries"#);
+                    return Expr::new(ExprType::Map(Map{entries}), start.to(self.previous()));
+                }
+                if self.check(vec![TokenType::THIS]) {
+                    return Expr::new(ExprType::This(None), start);
+                }
+                if self.check(vec![TokenType::SUPER]) {
+                    self.consume(TokenType::DOT, r#"Expected "." after "super""#);
+                    let method = self.idConsume("Expected superclass method name");
+                    return Expr::new(ExprType::Super(Super{method, depth: None}), start.to(self.previous()));
                 }
                 if !self.end() {
                     if let TokenType::IDENTIFIER(n) = self.tokens[self.current].tokenType.clone() {
                         self.current += 1;
-                        return Expr::new(ExprType::Variable(n), line);
+                        return Expr::new(ExprType::Variable(Variable{name: n, depth: None}), start);
                     }
                 }
             },
         }
     
         self.error("Expected expression");
-        Expr::new(ExprType::Literal(Literal::Nil), line)
+        Expr::new(ExprType::Literal(Literal::Nil), start)
+    }
+    //Collects the text and expressions of "a${b}c", whose tokens are "a${", b and "}c"
+    fn interpolation(&mut self) -> Expr {
+        let start = self.peek();
+        let mut parts = Vec::new();
+        loop {
+            match self.tokens[self.current].tokenType.clone() {
+                TokenType::INTERPOLATION(s) => {
+                    self.current += 1;
+                    if !s.is_empty() {
+                        parts.push(Part::Text(s));
+                    }
+                    parts.push(Part::Value(self.expression()));
+                },
+                TokenType::STRING(s) => {
+                    self.current += 1;
+                    if !s.is_empty() {
+                        parts.push(Part::Text(s));
+                    }
+                    break;
+                },
+                _ => {
+                    self.error("Expected end of string interpolation");
+                    break;
+                },
+            }
+        }
+        Expr::new(ExprType::Interpolation(Interpolation{parts}), start.to(self.previous()))
     }
     fn error(&mut self, message: &str) {
-        self.errors.push(LoxStaticError::new(self.tokens[self.current].line, message));
+        if self.panicking { return; }
+        self.panicking = true;
+        self.errors.push(LoxStaticError::new(self.peek(), message));
         self.synch();
     }
     fn consume(&mut self, token: TokenType, message: &str) {
         if self.tokens[self.current].tokenType != token {
             self.error(message);
         }
-        if !self.end() {
+        else if !self.end() {
             self.current += 1;
         }
     }
@@ -361,6 +571,11 @@ impl Parser
                 TokenType::WHILE => return,
                 TokenType::PRINT => return,
                 TokenType::RETURN => return,
+                TokenType::BREAK => return,
+                TokenType::CONTINUE => return,
+                TokenType::THROW => return,
+                TokenType::TRY => return,
+                TokenType::IMPORT => return,
                 _ => (),
             }
             self.current += 1
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use crate::expr::*;
+use crate::expr::stmt::{Stmt, StmtType, Decl, DeclType, Class};
+use crate::span::Span;
+use crate::LoxStaticError;
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType
+{
+    None,
+    Function,
+    Method,
+    Initialiser,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType
+{
+    None,
+    Class,
+}
+
+//A variable declared in a local scope
+struct Local
+{
+    //Whether its initialiser has finished resolving
+    defined: bool,
+    //A function declared later in the block. Only the functions nested before its declaration
+    //can see it, as they may run once it exists, while the code around them still sees outer variables
+    pending: bool,
+    span: Span,
+}
+
+//Works out how many scopes lie between each variable use and its declaration,
+//so the interpreter does not have to search for it by name
+pub struct Resolver
+{
+    scopes: Vec<HashMap<String, Local>>,
+    function: FunctionType,
+    class: ClassType,
+    //Number of loops around the current statement within the current function
+    loops: u32,
+    //The index of the innermost function's first scope, or 0 outside any function
+    boundary: usize,
+    errors: Vec<LoxStaticError>,
+}
+impl Default for Resolver
+{
+    fn default() -> Self
+    {
+        Resolver::new()
+    }
+}
+impl Resolver
+{
+    pub fn new() -> Self
+    {
+        Resolver{scopes: Vec::new(), function: FunctionType::None, class: ClassType::None, loops: 0, boundary: 0, errors: Vec::new()}
+    }
+    pub fn resolve(mut self, mut program: Vec<Decl>) -> (Vec<Decl>, Vec<LoxStaticError>)
+    {
+        self.decls(&mut program);
+        (program, self.errors)
+    }
+    fn decls(&mut self, decls: &mut Vec<Decl>)
+    {
+        //Lets local functions call those declared after them, as in mutual recursion
+        if let Some(scope) = self.scopes.last_mut() {
+            for decl in decls.iter() {
+                if let DeclType::FunDecl(f) = &decl.declType {
+                    scope.entry(f.name.clone()).or_insert(Local{defined: true, pending: true, span: f.span});
+                }
+            }
+        }
+        for decl in decls {
+            self.decl(decl);
+        }
+    }
+    fn decl(&mut self, decl: &mut Decl)
+    {
+        let span = decl.span;
+        match &mut decl.declType {
+            DeclType::VarDecl(name, value) => {
+                self.declare(name, span);
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+                self.define(name);
+            },
+            DeclType::FunDecl(func) => {
+                self.declare(&func.name, func.span);
+                self.define(&func.name);
+                self.function(&func.params, &mut func.body, FunctionType::Function);
+            },
+            DeclType::Import(_, name) => {
+                self.declare(name, span);
+                self.define(name);
+            },
+            DeclType::ClassDecl(class) => self.class(class),
+            DeclType::Stmt(stmt) => self.stmt(stmt),
+        }
+    }
+    fn class(&mut self, class: &mut Class)
+    {
+        let enclosing = self.class;
+        self.class = ClassType::Class;
+        self.declare(&class.name, class.span);
+        self.define(&class.name);
+        if let Some(s) = &mut class.superclass {
+            if let ExprType::Variable(v) = &s.exprType {
+                if v.name == class.name {
+                    self.errors.push(LoxStaticError::new(s.span, "A class cannot inherit from itself"));
+                }
+            }
+            self.expr(s);
+            self.beginScope();
+            self.define("super");
+        }
+        self.beginScope();
+        self.define("this");
+        for method in &mut class.methods {
+            let kind = match method.name.as_str() {
+                "init" => FunctionType::Initialiser,
+                _ => FunctionType::Method,
+            };
+            self.function(&method.params, &mut method.body, kind);
+        }
+        self.endScope();
+        if class.superclass.is_some() {
+            self.endScope();
+        }
+        self.class = enclosing;
+    }
+    fn function(&mut self, params: &[Parameter], body: &mut Vec<Decl>, kind: FunctionType)
+    {
+        let enclosing = self.function;
+        let enclosingLoops = self.loops;
+        let enclosingBoundary = self.boundary;
+        self.function = kind;
+        self.loops = 0;
+        self.beginScope();
+        self.boundary = self.scopes.len() - 1;
+        for param in params {
+            self.declare(&param.name, param.span);
+            self.define(&param.name);
+        }
+        self.decls(body);
+        self.endScope();
+        self.function = enclosing;
+        self.loops = enclosingLoops;
+        self.boundary = enclosingBoundary;
+    }
+    fn stmt(&mut self, stmt: &mut Stmt)
+    {
+        let span = stmt.span;
+        match &mut stmt.stmtType {
+            StmtType::ExprStmt(e) => self.expr(e),
+            StmtType::PrintStmt(e) => self.expr(e),
+            StmtType::IfStmt(c, i, e) => {
+                self.expr(c);
+                self.stmt(i);
+                if let Some(e) = e {
+                    self.stmt(e);
+                }
+            },
+            StmtType::WhileStmt(c, b) => {
+                self.expr(c);
+                self.loops += 1;
+                self.stmt(b);
+                self.loops -= 1;
+            },
+            StmtType::ForStmt(f) => {
+                self.beginScope();
+                if let Some(i) = &mut f.initialiser {
+                    self.decl(i);
+                }
+                if let Some(c) = &mut f.condition {
+                    self.expr(c);
+                }
+                self.loops += 1;
+                self.stmt(&mut f.body);
+                self.loops -= 1;
+                if let Some(i) = &mut f.increment {
+                    self.expr(i);
+                }
+                self.endScope();
+            },
+            StmtType::BreakStmt => {
+                if self.loops == 0 {
+                    self.errors.push(LoxStaticError::new(span, r#"Cannot use "break" outside of a loop"#));
+                }
+            },
+            StmtType::ContinueStmt => {
+                if self.loops == 0 {
+                    self.errors.push(LoxStaticError::new(span, r#"Cannot use "continue" outside of a loop"#));
+                }
+            },
+            StmtType::ReturnStmt(e) => {
+                match (self.function, e.as_ref()) {
+                    (FunctionType::None, _) => self.errors.push(LoxStaticError::new(span, "Cannot return from outside a function")),
+                    (FunctionType::Initialiser, Some(e)) if !matches!(e.exprType, ExprType::Literal(Literal::Nil)) => {
+                        self.errors.push(LoxStaticError::new(e.span, "Cannot return a value from an initialiser"));
+                    },
+                    _ => (),
+                }
+                if let Some(e) = e {
+                    self.expr(e);
+                }
+            },
+            StmtType::ThrowStmt(e) => self.expr(e),
+            StmtType::TryStmt(b, c, f) => {
+                self.block(b);
+                //The caught error lives in the same scope as the catch body
+                if let Some((name, c)) = c {
+                    self.beginScope();
+                    self.declare(&name.name, name.span);
+                    self.define(&name.name);
+                    self.decls(c);
+                    self.endScope();
+                }
+                if let Some(f) = f {
+                    self.block(f);
+                }
+            },
+            StmtType::Block(b) => self.block(b),
+        }
+    }
+    fn block(&mut self, decls: &mut Vec<Decl>)
+    {
+        self.beginScope();
+        self.decls(decls);
+        self.endScope();
+    }
+    fn expr(&mut self, expr: &mut Expr)
+    {
+        let span = expr.span;
+        match &mut expr.exprType {
+            ExprType::Literal(_) => (),
+            ExprType::Variable(v) => {
+                if let Some(Local{defined: false, span: declared, ..}) = self.scopes.last().and_then(|s| s.get(&v.name)) {
+                    let error = LoxStaticError::new(span, "Cannot read local variable in its own initialiser");
+                    self.errors.push(error.withNote(*declared, "variable declared here"));
+                }
+                v.depth = self.depth(&v.name);
+            },
+            ExprType::Assignment(a) => {
+                self.expr(&mut a.expr);
+                a.depth = self.depth(&a.id);
+            },
+            ExprType::Call(c) => {
+                self.expr(&mut c.callee);
+                for arg in &mut c.arguments {
+                    self.expr(arg);
+                }
+            },
+            ExprType::Unary(u) => self.expr(&mut u.expr),
+            ExprType::Logical(l) => {
+                self.expr(&mut l.lexpr);
+                self.expr(&mut l.rexpr);
+            },
+            ExprType::Binary(b) => {
+                self.expr(&mut b.lexpr);
+                self.expr(&mut b.rexpr);
+            },
+            ExprType::Grouping(g) => self.expr(&mut g.expr),
+            ExprType::Get(g) => self.expr(&mut g.object),
+            ExprType::Set(s) => {
+                self.expr(&mut s.value);
+                self.expr(&mut s.object);
+            },
+            ExprType::This(depth) => {
+                if self.class == ClassType::None {
+                    self.errors.push(LoxStaticError::new(span, r#"Cannot use "this" outside of a class"#));
+                }
+                *depth = self.depth("this");
+            },
+            ExprType::Super(s) => s.depth = self.depth("super"),
+            ExprType::List(l) => {
+                for element in &mut l.elements {
+                    self.expr(element);
+                }
+            },
+            ExprType::Map(m) => {
+                for (k, v) in &mut m.entries {
+                    self.expr(k);
+                    self.expr(v);
+                }
+            },
+            ExprType::Index(i) => {
+                self.expr(&mut i.object);
+                self.expr(&mut i.index);
+            },
+            ExprType::Lambda(l) => self.function(&l.params, &mut l.body, FunctionType::Function),
+            ExprType::Interpolation(i) => {
+                for part in &mut i.parts {
+                    if let Part::Value(value) = part {
+                        self.expr(value);
+                    }
+                }
+            },
+            ExprType::IndexSet(i) => {
+                self.expr(&mut i.object);
+                self.expr(&mut i.index);
+                self.expr(&mut i.value);
+            },
+        }
+    }
+    fn beginScope(&mut self)
+    {
+        self.scopes.push(HashMap::new());
+    }
+    fn endScope(&mut self)
+    {
+        self.scopes.pop();
+    }
+    fn declare(&mut self, name: &str, span: Span)
+    {
+        if let Some(scope) = self.scopes.last_mut() {
+            if let Some(previous) = scope.get(name).filter(|l| !l.pending) {
+                let error = LoxStaticError::new(span, format!("Variable {} is already declared in this scope", name).as_str());
+                self.errors.push(error.withNote(previous.span, "previously declared here"));
+            }
+            scope.insert(name.to_string(), Local{defined: false, pending: false, span});
+        }
+    }
+    fn define(&mut self, name: &str)
+    {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.entry(name.to_string()).or_insert(Local{defined: true, pending: false, span: Span::default()}).defined = true;
+        }
+    }
+    fn depth(&self, name: &str) -> Option<usize>
+    {
+        let (i, _) = self.scopes.iter().enumerate().rev()
+            .find(|(i, s)| s.get(name).is_some_and(|l| !l.pending || *i < self.boundary))?;
+        Some(self.scopes.len() - 1 - i)
+    }
+}
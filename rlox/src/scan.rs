@@ -1,135 +1,282 @@
-use crate::token::{Token, TokenType, RESERVED};
+use std::iter::Peekable;
+use std::str::CharIndices;
+use crate::token::{Token, TokenType, Comment, RESERVED};
+use crate::span::Span;
 use crate::LoxStaticError;
 
-pub fn scan(source: String) -> (Vec<Token>, Vec<LoxStaticError>)
+//Walks the characters of the source, keeping track of where it is
+#[derive(Clone)]
+struct Source<'a>
+{
+    text: &'a str,
+    id: u32,
+    chars: Peekable<CharIndices<'a>>,
+    line: u32,
+    lineStart: usize,
+}
+impl<'a> Source<'a>
+{
+    fn new(text: &'a str, id: u32) -> Source<'a>
+    {
+        Source{ text, id, chars: text.char_indices().peekable(), line: 1, lineStart: 0 }
+    }
+    fn next(&mut self) -> Option<char>
+    {
+        let (i, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.lineStart = i + 1;
+        }
+        Some(c)
+    }
+    fn peek(&mut self) -> Option<char>
+    {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+    fn peekNext(&self) -> Option<char>
+    {
+        let mut ahead = self.chars.clone();
+        ahead.next();
+        ahead.peek().map(|(_, c)| *c)
+    }
+    fn offset(&mut self) -> usize
+    {
+        match self.chars.peek() {
+            Some((i, _)) => *i,
+            None => self.text.len(),
+        }
+    }
+    //An empty span at the next character, to be passed to from once the token is scanned
+    fn mark(&mut self) -> Span
+    {
+        let offset = self.offset();
+        let column = self.text[self.lineStart..offset].chars().count() as u32 + 1;
+        Span::new(self.id, offset, 0, self.line, column)
+    }
+    fn from(&mut self, mark: Span) -> Span
+    {
+        Span{ len: self.offset() - mark.start, ..mark }
+    }
+}
+
+//Scans the rest of a string literal, up to the closing quote or the "${" that starts
+//an interpolated expression. Returns the text and whether it stopped at an interpolation
+fn string(source: &mut Source, start: Span, errors: &mut Vec<LoxStaticError>) -> (String, bool)
+{
+    let mut string = String::new();
+    loop {
+        let escapeStart = source.mark();
+        match source.next() {
+            Some('"') => return (string, false),
+            Some('$') if source.peek() == Some('{') => {
+                source.next();
+                return (string, true);
+            },
+            Some('\\') => {
+                match escape(source) {
+                    Ok(c) => string.push(c),
+                    Err(message) => errors.push(LoxStaticError::new(source.from(escapeStart), message)),
+                }
+            },
+            Some(nc) => string.push(nc),
+            None => {
+                errors.push(LoxStaticError::new(source.from(start), "Unterminated string"));
+                return (string, false);
+            },
+        }
+    }
+}
+
+//Decodes the escape sequence following a backslash
+fn escape(source: &mut Source) -> Result<char, &'static str>
+{
+    match source.next() {
+        Some('n') => Ok('\n'),
+        Some('t') => Ok('\t'),
+        Some('r') => Ok('\r'),
+        Some('0') => Ok('\0'),
+        Some('"') => Ok('"'),
+        Some('\\') => Ok('\\'),
+        Some('$') => Ok('$'),
+        Some('u') => {
+            if source.peek() != Some('{') {
+                return Err(r#"Expected "{" after "\u""#);
+            }
+            source.next();
+            let mut hex = String::new();
+            loop {
+                match source.peek() {
+                    Some('}') => { source.next(); break },
+                    Some(c) if c.is_ascii_hexdigit() && hex.len() < 6 => { hex.push(c); source.next(); },
+                    _ => return Err("Invalid unicode escape"),
+                }
+            }
+            match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                Some(c) => Ok(c),
+                None => Err("Invalid unicode escape"),
+            }
+        },
+        _ => Err("Invalid escape sequence"),
+    }
+}
+
+//id is the number spans use to refer to this source
+pub fn scan(source: String, id: u32) -> (Vec<Token>, Vec<LoxStaticError>)
+{
+    let (tokens, _, errors) = scanTrivia(source, id);
+    (tokens, errors)
+}
+
+//Like scan, but also returns the comments in the order they appear
+pub fn scanTrivia(source: String, id: u32) -> (Vec<Token>, Vec<Comment>, Vec<LoxStaticError>)
 {
     let mut errors: Vec<LoxStaticError> = Vec::new();
-    let mut source = source.chars().peekable();
+    let mut comments: Vec<Comment> = Vec::new();
+    let mut source = Source::new(&source, id);
     let mut tokens: Vec<Token> = Vec::new();
-    let mut line: u32 = 1;
-    let mut addToken = |tokenType, line| {tokens.push(Token::new(tokenType, line))};
+    let mut addToken = |tokenType, span| {tokens.push(Token::new(tokenType, span))};
+    //How many braces are open inside each "${" that has not been closed yet
+    let mut interpolations: Vec<u32> = Vec::new();
 
-    while let Some(c) = source.next() {
+    loop {
+        let start = source.mark();
+        let c = match source.next() {
+            Some(c) => c,
+            None => break,
+        };
         match c {
             ' ' => (),
             '\r' => (),
             '\t' => (),
-            '\n' => line += 1,
-            '(' => addToken(TokenType::LEFT_PAREN, line),
-            ')' => addToken(TokenType::RIGHT_PAREN, line),
-            '{' => addToken(TokenType::LEFT_BRACE, line),
-            '}' => addToken(TokenType::RIGHT_BRACE, line),
-            ',' => addToken(TokenType::COMMA, line),
-            '.' => addToken(TokenType::DOT, line),
-            '-' => addToken(TokenType::MINUS, line),
-            '+' => addToken(TokenType::PLUS, line),
-            ';' => addToken(TokenType::SEMICOLON, line),
-            '*' => addToken(TokenType::STAR, line),
+            '\n' => (),
+            '(' => addToken(TokenType::LEFT_PAREN, source.from(start)),
+            ')' => addToken(TokenType::RIGHT_PAREN, source.from(start)),
+            '{' => {
+                if let Some(depth) = interpolations.last_mut() {
+                    *depth += 1;
+                }
+                addToken(TokenType::LEFT_BRACE, source.from(start));
+            },
+            '}' => {
+                match interpolations.last_mut() {
+                    Some(0) => {
+                        interpolations.pop();
+                        let (string, more) = string(&mut source, start, &mut errors);
+                        if more {
+                            interpolations.push(0);
+                            addToken(TokenType::INTERPOLATION(string), source.from(start));
+                        }
+                        else {
+                            addToken(TokenType::STRING(string), source.from(start));
+                        }
+                    },
+                    Some(depth) => {
+                        *depth -= 1;
+                        addToken(TokenType::RIGHT_BRACE, source.from(start));
+                    },
+                    None => addToken(TokenType::RIGHT_BRACE, source.from(start)),
+                }
+            },
+            '[' => addToken(TokenType::LEFT_BRACKET, source.from(start)),
+            ']' => addToken(TokenType::RIGHT_BRACKET, source.from(start)),
+            ',' => addToken(TokenType::COMMA, source.from(start)),
+            ':' => addToken(TokenType::COLON, source.from(start)),
+            '.' => addToken(TokenType::DOT, source.from(start)),
+            '-' => addToken(TokenType::MINUS, source.from(start)),
+            '+' => addToken(TokenType::PLUS, source.from(start)),
+            ';' => addToken(TokenType::SEMICOLON, source.from(start)),
+            '*' => addToken(TokenType::STAR, source.from(start)),
             '!' => {
                 if let Some('=') = source.peek() {
                     source.next();
-                    addToken(TokenType::BANG_EQUAL, line);
+                    addToken(TokenType::BANG_EQUAL, source.from(start));
                 }
                 else {
-                    addToken(TokenType::BANG, line);
+                    addToken(TokenType::BANG, source.from(start));
                 }
             }
             '=' => {
                 if let Some('=') = source.peek() {
                     source.next();
-                    addToken(TokenType::EQUAL_EQUAL, line);
+                    addToken(TokenType::EQUAL_EQUAL, source.from(start));
+                }
+                else if let Some('>') = source.peek() {
+                    source.next();
+                    addToken(TokenType::ARROW, source.from(start));
                 }
                 else {
-                    addToken(TokenType::EQUAL, line);
+                    addToken(TokenType::EQUAL, source.from(start));
                 }
             }
             '<' => {
                 if let Some('=') = source.peek() {
                     source.next();
-                    addToken(TokenType::LESS_EQUAL, line);
+                    addToken(TokenType::LESS_EQUAL, source.from(start));
                 }
                 else {
-                    addToken(TokenType::LESS, line);
+                    addToken(TokenType::LESS, source.from(start));
                 }
             }
             '>' => {
                 if let Some('=') = source.peek() {
                     source.next();
-                    addToken(TokenType::GREATER_EQUAL, line);
+                    addToken(TokenType::GREATER_EQUAL, source.from(start));
                 }
                 else {
-                    addToken(TokenType::GREATER, line);
+                    addToken(TokenType::GREATER, source.from(start));
                 }
             }
             '/' => {
                 if let Some('/') = source.peek() {
                     while let Some(nc) = source.peek() {
-                        if *nc != '\n' {
+                        if nc != '\n' {
                             source.next();
                         }
                         else {
                             break;
                         }
                     }
-                    
+                    let span = source.from(start);
+                    //A carriage return before the newline is not part of the comment
+                    let text = source.text[span.start..span.end()].trim_end().to_string();
+                    comments.push(Comment{ text, span });
                 }
                 else {
-                    addToken(TokenType::SLASH, line);
+                    addToken(TokenType::SLASH, source.from(start));
                 }
             }
             '"' => {
-                let mut string = String::new();
-                loop {
-                    match source.next() {
-                        Some(nc) => {
-                            match nc {
-                                '\n' => line += 1,
-                                '"' => break,
-                                _ => string.push(nc),
-                            }
-                        }
-                        None => {
-                            errors.push(LoxStaticError::new(line, "Unterminated string"));
-                            break;
-                        }
-                    }
+                let (string, more) = string(&mut source, start, &mut errors);
+                if more {
+                    interpolations.push(0);
+                    addToken(TokenType::INTERPOLATION(string), source.from(start));
+                }
+                else {
+                    addToken(TokenType::STRING(string), source.from(start));
                 }
-                addToken(TokenType::STRING(string), line);
             },
-            x if x.is_digit(10) => {
+            x if x.is_ascii_digit() => {
                 let mut str_rep = String::new();
                 str_rep.push(x);
                 let mut dot = false;
-                let mut last: Option<char>;
-                let mut end = false;
-                loop {
-                    last = source.next();
-                    match last {
-                        Some(nc) => {
-                            match nc {
-                                x if x.is_digit(10) => str_rep.push(nc),
-                                x if x == '.' => {
-                                    let next = source.peek().unwrap_or(&'_');
-                                    if !dot && next.is_digit(10){
-                                        str_rep.push(nc);
-                                        dot = true;
-                                    }
-                                    else { 
-                                        break; 
-                                    }
+                while let Some(nc) = source.peek() {
+                    match nc {
+                        x if x.is_ascii_digit() => str_rep.push(nc),
+                        '.' if !dot => {
+                            //Only part of the number if a digit follows, so "1.foo" is still a method call
+                            match source.peekNext() {
+                                Some(d) if d.is_ascii_digit() => {
+                                    str_rep.push(nc);
+                                    dot = true;
                                 },
                                 _ => break,
                             }
                         },
-                        None => { end = true; break },
+                        _ => break,
                     }
+                    source.next();
                 }
-                addToken(TokenType::NUMBER(str_rep.parse::<f64>().unwrap()), line);
-                if !end {
-                    let mut extra = scan(String::from(last.unwrap()));
-                    errors.append(&mut extra.1);
-                    addToken(extra.0[0].tokenType.clone(), line);
-                }
+                addToken(TokenType::NUMBER(str_rep.parse::<f64>().unwrap()), source.from(start));
             },
             x if x.is_alphabetic() || x == '_' => {
 
@@ -137,21 +284,25 @@ pub fn scan(source: String) -> (Vec<Token>, Vec<LoxStaticError>)
                 ident.push(x);
                 while let Some(nc) = source.peek() {
                     match nc {
-                        x if x.is_alphanumeric() || *x == '_' => ident.push(*x),
+                        x if x.is_alphanumeric() || x == '_' => ident.push(x),
                         _ => break,
                     }
                     source.next();
                 }
                 match RESERVED.get(ident.as_str()) {
-                    Some(t) => addToken(t.clone(), line),
-                    None => addToken(TokenType::IDENTIFIER(ident), line),
+                    Some(t) => addToken(t.clone(), source.from(start)),
+                    None => addToken(TokenType::IDENTIFIER(ident), source.from(start)),
                 }
             },
             
-            _ => errors.push(LoxStaticError::new(line, "Unexpected character")),
+            _ => errors.push(LoxStaticError::new(source.from(start), "Unexpected character")),
         }
     }
 
-    tokens.push(Token::new(TokenType::EOF, line));
-    (tokens, errors)
+    let end = source.mark();
+    if !interpolations.is_empty() {
+        errors.push(LoxStaticError::new(end, "Unterminated string interpolation"));
+    }
+    tokens.push(Token::new(TokenType::EOF, end));
+    (tokens, comments, errors)
 }
@@ -0,0 +1,41 @@
+use serde::{Serialize, Deserialize};
+
+//The source of spans that do not belong to any loaded file, such as those read from JSON
+pub const NO_SOURCE: u32 = u32::MAX;
+fn noSource() -> u32
+{
+    NO_SOURCE
+}
+
+//A region of a source file. source identifies the file among those the program
+//has loaded, start and len are byte offsets, while line and column (both
+//counted from 1) give the position of the first character
+//Missing positions are read as zero, so tools can build trees without them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Span
+{
+    //Only meaningful to the program that loaded the file, so it is left out of JSON
+    #[serde(skip, default = "noSource")]
+    pub source: u32,
+    pub start: usize,
+    pub len: usize,
+    pub line: u32,
+    pub column: u32,
+}
+impl Span
+{
+    pub fn new(source: u32, start: usize, len: usize, line: u32, column: u32) -> Span
+    {
+        Span{ source, start, len, line, column }
+    }
+    pub fn end(&self) -> usize
+    {
+        self.start + self.len
+    }
+    //Covers everything from the start of this span to the end of the other
+    pub fn to(self, other: Span) -> Span
+    {
+        Span{ len: other.end().saturating_sub(self.start), ..self }
+    }
+}
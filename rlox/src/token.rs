@@ -1,17 +1,28 @@
 use phf::{phf_map, Map};
+use serde::{Serialize, Deserialize};
+use crate::span::Span;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token
 {
     pub tokenType: TokenType,
-    pub line: u32,
+    pub span: Span,
 }
 impl Token
 {
-    pub fn new(tokenType: TokenType, line: u32) -> Token { Token{tokenType, line} }
+    pub fn new(tokenType: TokenType, span: Span) -> Token { Token{tokenType, span} }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+//A comment. The parser never sees these, but they are kept for tools that rewrite source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment
+{
+    //The text of the comment, including the leading "//"
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TokenType
 {
     // Single-character tokens.
@@ -19,7 +30,10 @@ pub enum TokenType
     RIGHT_PAREN,
     LEFT_BRACE,
     RIGHT_BRACE,
+    LEFT_BRACKET,
+    RIGHT_BRACKET,
     COMMA,
+    COLON,
     DOT,
     MINUS,
     PLUS,
@@ -32,6 +46,7 @@ pub enum TokenType
     BANG_EQUAL,
     EQUAL,
     EQUAL_EQUAL,
+    ARROW,
     GREATER,
     GREATER_EQUAL,
     LESS,
@@ -40,39 +55,55 @@ pub enum TokenType
     // Literals.
     IDENTIFIER(String),
     STRING(String),
+    //A piece of string followed by "${"; the interpolated expression comes next
+    INTERPOLATION(String),
     NUMBER(f64),
     BOOL(bool),
 
     // Keywords.
     AND,
+    BREAK,
     CLASS,
+    CONTINUE,
+    CATCH,
     ELSE,
+    FINALLY,
     FUN,
     FOR,
     IF,
+    IMPORT,
     NIL,
     OR,
     PRINT,
     RETURN,
     SUPER,
     THIS,
+    THROW,
+    TRY,
     VAR,
     WHILE,
     EOF,
 }
 pub const RESERVED: Map<&'static str, TokenType> = phf_map! {
     "and" => TokenType::AND,
+    "break" => TokenType::BREAK,
+    "catch" => TokenType::CATCH,
     "class" => TokenType::CLASS,
+    "continue" => TokenType::CONTINUE,
     "else" => TokenType::ELSE,
     "false" => TokenType::BOOL(false),
+    "finally" => TokenType::FINALLY,
     "for" => TokenType::FOR,
     "fun" => TokenType::FUN,
     "if" => TokenType::IF,
+    "import" => TokenType::IMPORT,
     "nil" => TokenType::NIL,
     "or" => TokenType::OR,
     "print" => TokenType::PRINT,
     "return" => TokenType::RETURN,
     "super" => TokenType::SUPER,
+    "throw" => TokenType::THROW,
+    "try" => TokenType::TRY,
     "this" => TokenType::THIS,
     "true" => TokenType::BOOL(true),
     "var" => TokenType::VAR,
@@ -0,0 +1,82 @@
+#![allow(non_snake_case)]
+//Drives the interpreter the way a host program embedding it would
+use rlox::{Interpreter, LoxError, LoxValue, LoxRuntimeErrorType, Capture};
+use rlox::interpret::loxType::LoxType;
+use rlox::interpret::loxRuntimeError::{LoxIndexError, LoxTypeError};
+
+#[test]
+fn globals() -> Result<(), Box<dyn std::error::Error>>
+{
+    let mut lox = Interpreter::new();
+    let output = Capture::new();
+    lox.set_output(output.clone());
+    lox.set_global("width", 6);
+    lox.set_global("name", "box");
+    lox.set_global("sizes", vec![1.5, 2.5]);
+    lox.eval_str("var area = width * 7; print name + \":\"; print sizes[1];")?;
+    assert_eq!(output.text(), "box:\n2.5\n");
+    let area: f64 = lox.get_global("area").unwrap().try_into()?;
+    assert_eq!(area, 42.0);
+    //Globals set from the host can be overwritten by the script and read back
+    lox.set_global("flag", false);
+    let flag: bool = lox.eval_str("flag = !flag; flag;")?.try_into()?;
+    assert!(flag);
+    assert!(lox.get_global("missing").is_none());
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn natives()
+{
+    let mut lox = Interpreter::new();
+    lox.define_native("at", 2, |a| {
+        let list: Vec<LoxValue> = a[0].clone().try_into().map_err(|e| LoxRuntimeErrorType::from(LoxTypeError::Mismatch(e)))?;
+        let index: f64 = a[1].clone().try_into().map_err(|e| LoxRuntimeErrorType::from(LoxTypeError::Mismatch(e)))?;
+        list.get(index as usize).cloned().ok_or(LoxIndexError::OutOfRange(index, list.len()).into())
+    });
+    let value: f64 = lox.eval_str("at([4, 5, 6], 1);").unwrap().try_into().unwrap();
+    assert_eq!(value, 5.0);
+    //An error returned by a native surfaces as a runtime error at the call
+    let error = lox.eval_str("at([4, 5, 6], 3);").unwrap_err();
+    let LoxError::Runtime(error) = &error else { panic!("expected a runtime error, got {}", error) };
+    assert!(matches!(error.errorType, LoxRuntimeErrorType::IndexError(LoxIndexError::OutOfRange(i, 3)) if i == 3.0));
+    assert_eq!(error.errorType.to_string(), "Index 3 is out of range for a list of length 3");
+    //and can be caught by the script like any other
+    let caught: String = lox.eval_str("var k; try { at(\"abc\", 0); } catch (e) { k = e.kind; } k;").unwrap().try_into().unwrap();
+    assert_eq!(caught, "TypeError");
+}
+
+#[test]
+fn conversions()
+{
+    assert!(matches!(LoxValue::from(2), LoxValue::Num(n) if n == 2.0));
+    assert!(matches!(LoxValue::from(true), LoxValue::Bool(true)));
+    assert!(matches!(LoxValue::from(String::from("a")), LoxValue::Str(s) if s == "a"));
+    assert!(matches!(LoxValue::from(()), LoxValue::Nil));
+    assert!(matches!(LoxValue::from(None::<f64>), LoxValue::Nil));
+    assert!(matches!(LoxValue::from(Some("b")), LoxValue::Str(s) if s == "b"));
+    assert_eq!(f64::try_from(LoxValue::Num(1.5)).unwrap(), 1.5);
+    assert!(!bool::try_from(LoxValue::Bool(false)).unwrap());
+    assert_eq!(String::try_from(LoxValue::from("c")).unwrap(), "c");
+    let list = Vec::<LoxValue>::try_from(LoxValue::from(vec!["x", "y"])).unwrap();
+    assert_eq!(list.len(), 2);
+    assert!(matches!(&list[1], LoxValue::Str(s) if s == "y"));
+    //A value of the wrong type reports what it was and what was wanted
+    let mismatch = f64::try_from(LoxValue::from("1")).unwrap_err();
+    assert!(matches!(mismatch.found, LoxType::Str));
+    assert!(matches!(mismatch.expected[..], [LoxType::Num]));
+    assert!(bool::try_from(LoxValue::Nil).is_err());
+    assert!(Vec::<LoxValue>::try_from(LoxValue::Num(0.0)).is_err());
+}
+
+#[test]
+fn exit()
+{
+    let mut lox = Interpreter::new();
+    let output = Capture::new();
+    lox.set_output(output.clone());
+    let error = lox.eval_str("print 1; try { exit(3); } catch (e) { print 2; } print 4;").unwrap_err();
+    assert!(matches!(error, LoxError::Exit(3)), "expected exit, got {}", error);
+    assert_eq!(output.text(), "1\n");
+}
@@ -0,0 +1,78 @@
+#![allow(non_snake_case)]
+//Checks "rlox fmt" against hand-written layouts, and that formatting the golden
+//corpus keeps every comment and does nothing the second time
+use std::path::Path;
+use rlox::format::{format, Style};
+
+fn formatted(source: &str) -> String
+{
+    format(source, Style::default()).unwrap_or_else(|e| panic!("{}", e[0]))
+}
+
+#[test]
+fn layout()
+{
+    let source = "var   a=1;var b; // b\nfun add(x,y){return x+y;}\n\n\nif (a) print 1; else { print [1,2]; }\n";
+    let expected = "var a = 1;\nvar b; // b\nfun add(x, y) {\n  return x + y;\n}\n\nif (a) print 1;\nelse {\n  print [1, 2];\n}\n";
+    assert_eq!(formatted(source), expected);
+}
+
+#[test]
+fn sugar()
+{
+    let source = "for (var i = 0; i < 3; i = i + 1) {}\nfor (;;) break;\nfor (i = 0; i < 3;) i = i + 1;\nvar s = \"a${ b }c\\n\";\nvar t = \"${\"${s}\"}$\";\nvar f = (x) => x;\nfun f() { return; }\nimport \"lib/shapes.lox\" as shapes;\n";
+    let expected = "for (var i = 0; i < 3; i = i + 1) {}\nfor (;;) break;\nfor (i = 0; i < 3;) i = i + 1;\nvar s = \"a${b}c\\n\";\nvar t = \"${\"${s}\"}$\";\nvar f = (x) => x;\nfun f() {\n  return;\n}\nimport \"lib/shapes.lox\";\n";
+    assert_eq!(formatted(source), expected);
+}
+
+#[test]
+fn comments()
+{
+    let source = "// top\nclass A {\n  // before m\n  m() {\n    // inside\n  } // after m\n}\n// bottom\n";
+    assert_eq!(formatted(source), source);
+}
+
+#[test]
+fn placement()
+{
+    //Comments stay before the token they were written before, even inside a statement
+    let source = "print f(\n  a, // a\n  b,\n  // c\n  c\n);\nvar x = 1 + // why\n  2;\nif (x) {\n  print 1;\n} // then\nelse {\n  print 2;\n}\ntry {}\n// catch\ncatch (e) {}\n";
+    assert_eq!(formatted(source), source);
+}
+
+#[test]
+fn width()
+{
+    let source = "print call(first, second, third);\n";
+    let style = Style{ indent: 4, width: 20 };
+    let expected = "print call(\n    first,\n    second,\n    third\n);\n";
+    assert_eq!(format(source, style).unwrap_or_default(), expected);
+}
+
+fn corpus(dir: &Path, files: &mut Vec<std::path::PathBuf>)
+{
+    for entry in std::fs::read_dir(dir).expect("Could not read the test directory") {
+        let path = entry.expect("Could not read the test directory").path();
+        if path.is_dir() {
+            corpus(&path, files);
+        }
+        else if path.extension().is_some_and(|e| e == "lox") {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn idempotent()
+{
+    let mut files = Vec::new();
+    corpus(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox"), &mut files);
+    for file in files {
+        let source = std::fs::read_to_string(&file).expect("Could not read test file");
+        //Files testing syntax errors cannot be formatted
+        let Ok(once) = format(&source, Style::default()) else { continue };
+        assert_eq!(once.matches("//").count(), source.matches("//").count(), "{} lost comments", file.display());
+        let twice = format(&once, Style::default()).unwrap_or_else(|e| panic!("{} formatted to invalid code: {}", file.display(), e[0]));
+        assert_eq!(once, twice, "{} changed when formatted again", file.display());
+    }
+}
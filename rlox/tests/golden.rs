@@ -0,0 +1,213 @@
+#![allow(non_snake_case)]
+//Runs every .lox file under tests/lox and checks it against the expectations
+//written in its comments, in the format of the craftinginterpreters test suite:
+//  // expect: output            a line the script prints
+//  // expect runtime error: msg the error that stops the script, on this line
+//  // Error at 'x': msg         a static error on this line
+//  // [line N] Error at 'x': msg a static error on line N
+//The location after "Error" is not compared, as rlox points at whole
+//expressions rather than single tokens.
+//
+//Files in directories named lib are only there to be imported, and are not run.
+//
+//Set LOX_INTERPRETER to the path of an interpreter binary (such as rlox2) to
+//run the files through it instead, judging errors by exit code and stderr.
+//Expectations marked "[c line N]" only apply to such a binary
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use rlox::{Interpreter, Capture, LoxError};
+
+#[derive(Default)]
+struct Expectations
+{
+    output: Vec<String>,
+    //Each as its line and message
+    runtimeError: Option<(u32, String)>,
+    staticErrors: Vec<(u32, String)>,
+}
+
+//The results of running a script, in the same form as the expectations
+struct Outcome
+{
+    output: Vec<String>,
+    runtimeError: Option<(u32, String)>,
+    staticErrors: Vec<(u32, String)>,
+}
+
+fn expectations(source: &str, external: bool) -> Expectations
+{
+    let mut expected = Expectations::default();
+    for (i, line) in source.lines().enumerate() {
+        let lineNo = i as u32 + 1;
+        let Some(comment) = line.find("//").map(|c| line[c+2..].trim()) else { continue };
+        if let Some(output) = comment.strip_prefix("expect: ") {
+            expected.output.push(output.to_string());
+        }
+        else if let Some(error) = comment.strip_prefix("expect runtime error: ") {
+            expected.runtimeError = Some((lineNo, error.to_string()));
+        }
+        else if let Some(error) = staticError(comment, lineNo, external) {
+            expected.staticErrors.push(error);
+        }
+    }
+    expected
+}
+
+//Parses "Error at ...: msg", optionally preceded by "[line N]" or "[c line N]"
+fn staticError(comment: &str, lineNo: u32, external: bool) -> Option<(u32, String)>
+{
+    let (line, rest) = match comment.strip_prefix('[') {
+        Some(rest) => {
+            let (location, rest) = rest.split_once(']')?;
+            let line = match location.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["line", n] => n.parse().ok()?,
+                ["c", "line", n] if external => n.parse().ok()?,
+                _ => return None,
+            };
+            (line, rest.trim())
+        },
+        None => (lineNo, comment),
+    };
+    let rest = rest.strip_prefix("Error")?;
+    let (_, message) = rest.split_once(": ")?;
+    Some((line, message.to_string()))
+}
+
+fn runInProcess(path: &Path) -> Outcome
+{
+    let mut interpreter = Interpreter::new();
+    let output = Capture::new();
+    interpreter.set_output(output.clone());
+    let result = interpreter.run_file(path);
+    let mut outcome = Outcome{ output: Vec::new(), runtimeError: None, staticErrors: Vec::new() };
+    match result {
+        Ok(_) => (),
+        Err(LoxError::Static(errors)) => {
+            outcome.staticErrors = errors.iter().map(|e| (e.span.line, e.message.clone())).collect();
+        },
+        Err(LoxError::Runtime(e)) => outcome.runtimeError = Some((e.span.line, format!("{}", e.errorType))),
+        //Tests that exit early do so successfully, like scripts that run to the end
+        Err(LoxError::Exit(0)) => (),
+        Err(e) => panic!("{}", e),
+    }
+    outcome.output = output.text().lines().map(|l| l.to_string()).collect();
+    outcome
+}
+
+fn runExternal(binary: &str, path: &Path, expected: &Expectations) -> Vec<String>
+{
+    let result = Command::new(binary).arg(path).output().expect("Could not run the interpreter");
+    let stdout: Vec<String> = String::from_utf8_lossy(&result.stdout).lines().map(|l| l.to_string()).collect();
+    let stderr = String::from_utf8_lossy(&result.stderr).to_string();
+    let mut failures = compareOutput(&expected.output, &stdout);
+    let code = result.status.code().unwrap_or(-1);
+    let expectedCode = if !expected.staticErrors.is_empty() { 65 } else if expected.runtimeError.is_some() { 70 } else { 0 };
+    if code != expectedCode {
+        failures.push(format!("Expected exit code {} but got {}", expectedCode, code));
+    }
+    for (_, message) in expected.staticErrors.iter().chain(expected.runtimeError.iter()) {
+        if !stderr.contains(message.as_str()) {
+            failures.push(format!("Expected error \"{}\" in stderr:\n{}", message, stderr));
+        }
+    }
+    failures
+}
+
+fn compareOutput(expected: &[String], actual: &[String]) -> Vec<String>
+{
+    let mut failures = Vec::new();
+    for (i, line) in expected.iter().enumerate() {
+        match actual.get(i) {
+            Some(a) if a == line => (),
+            Some(a) => failures.push(format!("Expected output \"{}\" but got \"{}\"", line, a)),
+            None => failures.push(format!("Missing expected output \"{}\"", line)),
+        }
+    }
+    for line in actual.iter().skip(expected.len()) {
+        failures.push(format!("Unexpected output \"{}\"", line));
+    }
+    failures
+}
+
+fn check(path: &Path) -> Vec<String>
+{
+    let source = std::fs::read_to_string(path).expect("Could not read test file");
+    if let Ok(binary) = std::env::var("LOX_INTERPRETER") {
+        return runExternal(&binary, path, &expectations(&source, true));
+    }
+    let expected = expectations(&source, false);
+    let outcome = runInProcess(path);
+    let mut failures = compareOutput(&expected.output, &outcome.output);
+    if expected.runtimeError != outcome.runtimeError {
+        let describe = |e: &Option<(u32, String)>| match e {
+            Some((line, message)) => format!("\"{}\" on line {}", message, line),
+            None => "none".to_string(),
+        };
+        failures.push(format!("Expected runtime error {} but got {}", describe(&expected.runtimeError), describe(&outcome.runtimeError)));
+    }
+    for error in &expected.staticErrors {
+        if !outcome.staticErrors.contains(error) {
+            failures.push(format!("Missing error \"{}\" on line {}", error.1, error.0));
+        }
+    }
+    for error in &outcome.staticErrors {
+        if !expected.staticErrors.contains(error) {
+            failures.push(format!("Unexpected error \"{}\" on line {}", error.1, error.0));
+        }
+    }
+    failures
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>)
+{
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir).expect("Could not read test directory")
+        .map(|e| e.expect("Could not read test directory").path())
+        .collect();
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            if entry.file_name().is_some_and(|n| n != "lib") {
+                collect(&entry, files);
+            }
+        }
+        else if entry.extension().is_some_and(|e| e == "lox") {
+            files.push(entry);
+        }
+    }
+}
+
+fn main()
+{
+    //Relative, so paths in error messages do not depend on where the repository is
+    std::env::set_current_dir(env!("CARGO_MANIFEST_DIR")).expect("Could not enter the package directory");
+    let root = Path::new("tests").join("lox");
+    //Like the built-in harness, any free argument filters the files to run
+    let filters: Vec<String> = std::env::args().skip(1).filter(|a| !a.starts_with('-')).collect();
+    let mut files = Vec::new();
+    collect(&root, &mut files);
+
+    let mut failed = 0;
+    let mut ran = 0;
+    for file in &files {
+        let name = file.strip_prefix(&root).unwrap_or(file).to_string_lossy().to_string();
+        if !filters.is_empty() && !filters.iter().any(|f| name.contains(f.as_str())) {
+            continue;
+        }
+        ran += 1;
+        let failures = check(file);
+        if failures.is_empty() {
+            println!("test {} ... ok", name);
+        }
+        else {
+            failed += 1;
+            println!("test {} ... FAILED", name);
+            for failure in failures {
+                println!("    {}", failure);
+            }
+        }
+    }
+    println!("\ntest result: {}. {} passed; {} failed", if failed == 0 { "ok" } else { "FAILED" }, ran - failed, failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
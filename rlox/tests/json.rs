@@ -0,0 +1,66 @@
+#![allow(non_snake_case)]
+//Checks that syntax trees survive being written to JSON and read back
+use rlox::{Interpreter, Capture, LoxError};
+
+fn output(interpreter: &mut Interpreter, program: Vec<rlox::expr::stmt::Decl>) -> String
+{
+    let capture = Capture::new();
+    interpreter.set_output(capture.clone());
+    if let Err(e) = interpreter.execute(program) {
+        panic!("{}", e);
+    }
+    capture.text()
+}
+
+#[test]
+fn roundTrip()
+{
+    let source = r#"
+        class Counter {
+          init() { this.n = 0; }
+          add(by) { this.n = this.n + by; return this; }
+        }
+        fun make() {
+          var c = Counter();
+          return (x) => c.add(x).n;
+        }
+        var f = make();
+        for (var i = 0; i < 3; i = i + 1) f(i);
+        print "total ${f(0)}";
+        print [1, 2][1] + {"k": 3}["k"];
+    "#;
+    let mut interpreter = Interpreter::new();
+    let program = interpreter.compile(source, "<test>").unwrap_or_else(|e| panic!("{}", e));
+    let json = serde_json::to_string(&program).expect("Could not serialize");
+    let expected = output(&mut interpreter, program);
+
+    let mut interpreter = Interpreter::new();
+    let program = interpreter.compile_json(&json).unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!(output(&mut interpreter, program), expected);
+    assert_eq!(expected, "total 3\n5\n");
+}
+
+#[test]
+fn handWritten()
+{
+    //Positions and resolver depths can be left out
+    let json = r#"[
+        {"declType": {"VarDecl": ["x", {"exprType": {"Literal": {"Num": 2}}, "span": {}}]}, "span": {"line": 1}},
+        {"declType": {"Stmt": {"stmtType": {"PrintStmt": {"exprType": {"Binary": {
+            "lexpr": {"exprType": {"Variable": {"name": "x"}}, "span": {}},
+            "operator": "Star",
+            "rexpr": {"exprType": {"Literal": {"Str": "a"}}, "span": {"line": 2, "column": 11}}
+        }}, "span": {"line": 2}}}, "span": {"line": 2}}}, "span": {"line": 2}}
+    ]"#;
+    let mut interpreter = Interpreter::new();
+    let program = interpreter.compile_json(json).unwrap_or_else(|e| panic!("{}", e));
+    match interpreter.execute(program) {
+        Err(LoxError::Runtime(e)) => {
+            assert_eq!(e.span.line, 2);
+            //Rendering must not look for a file the tree never came from
+            assert!(interpreter.render(&LoxError::Runtime(e), &rlox::diagnostic::Renderer::new(rlox::diagnostic::ColorChoice::Never)).contains("--> 2:11"));
+        },
+        _ => panic!("Expected a runtime error"),
+    }
+    assert!(matches!(interpreter.compile_json("[{}]"), Err(LoxError::Json(_))));
+}
@@ -0,0 +1,68 @@
+#![allow(non_snake_case)]
+//Checks which lines "rlox lint" warns about, and that allowing a lint on a line silences it
+use rlox::Interpreter;
+use rlox::loxStaticError::Severity;
+
+//Each warning as its line and lint
+fn warnings(source: &str) -> Vec<(u32, &'static str)>
+{
+    let warnings = Interpreter::new().lint(source, "<test>").unwrap_or_else(|e| panic!("{}", e));
+    warnings.iter().map(|w| match w.severity {
+        Severity::Warning(lint) => (w.span.line, lint),
+        Severity::Error => panic!("Lint reported an error: {}", w),
+    }).collect()
+}
+
+#[test]
+fn lints()
+{
+    let source = r#"
+        fun add(a, b) {
+          var unused = 1;
+          return a;
+          print "dead";
+        }
+        fun outer(_ignored) {
+          var x = 1;
+          { var x = 2; print x; }
+          print x;
+        }
+        add(1);
+        typo = 3;
+        if (1 < 2) print "yes";
+        while (true) break;
+    "#;
+    let expected = vec![(2, "unused"), (3, "unused"), (5, "unreachable"), (9, "shadow"), (12, "arity"), (13, "undeclared"), (14, "constant")];
+    assert_eq!(warnings(source), expected);
+}
+
+#[test]
+fn knownNames()
+{
+    //Globals may be declared after the functions using them, and built-in functions are globals too
+    let source = "fun set() { later = clock(); }\nvar later;\nfun f(x) { return x; }\nf = (a, b) => a + b;\nprint f(1, 2);\n";
+    assert_eq!(warnings(source), vec![]);
+    //Local functions may call those declared after them
+    let source = "{\n  fun even(n) { return n == 0 or odd(n - 1); }\n  fun odd(n) { return n != 0 and even(n - 1); }\n  print even(2);\n}\n";
+    assert_eq!(warnings(source), vec![]);
+}
+
+#[test]
+fn allow()
+{
+    let source = "fun f(a) {} // lint: allow(unused)\nf(); // lint: allow(shadow, arity)\nif (true) print 1; // lint: allow(unused)\n";
+    assert_eq!(warnings(source), vec![(3, "constant")]);
+    //An unused catch variable is reported on the line of its name, not where the try starts
+    let source = "try {\n  throw 1;\n} catch (e) { // lint: allow(unused)\n  print 2;\n}\ntry {\n  throw 1;\n} catch (e) {\n  print 3;\n}\n";
+    assert_eq!(warnings(source), vec![(8, "unused")]);
+}
+
+#[test]
+fn parameters()
+{
+    //Each parameter is reported where it is written, so it can be allowed on its own line
+    let source = "fun f(\n  a, // lint: allow(unused)\n  b\n) {}\nf(1, 2);\n";
+    assert_eq!(warnings(source), vec![(3, "unused")]);
+    let warnings = Interpreter::new().lint("fun g(x, y) { return x; }\ng(1, 2);\n", "<test>").unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!(warnings.iter().map(|w| (w.span.line, w.span.column)).collect::<Vec<_>>(), vec![(1, 10)]);
+}
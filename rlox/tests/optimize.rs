@@ -0,0 +1,35 @@
+#![allow(non_snake_case)]
+//Checks which parts of a program the optimizer folds or removes
+use rlox::Interpreter;
+use rlox::optimize::optimize;
+
+fn optimized(source: &str) -> String
+{
+    let program = Interpreter::new().compile(source, "<test>").unwrap_or_else(|e| panic!("{}", e));
+    optimize(program).iter().map(|d| d.to_string()).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn folding()
+{
+    let parsed = |source| Interpreter::new().compile(source, "<test>").unwrap_or_else(|e| panic!("{}", e))[0].to_string();
+    assert_eq!(optimized("print 60 * 60 * 24;"), parsed("print 86400;"));
+    assert_eq!(optimized("var s = (\"a\" + \"b\");"), parsed("var s = \"ab\";"));
+    assert_eq!(optimized("print x or 1 + 1;"), parsed("print x or 2;"));
+    assert_eq!(optimized("print true and x;"), parsed("print x;"));
+    assert_eq!(optimized("print !nil == (0 / 0 == 0 / 0);"), parsed("print false;"));
+    //Left as they were, so they fail when run
+    assert_eq!(optimized("print 1 + \"a\";"), parsed("print 1 + \"a\";"));
+    assert_eq!(optimized("print -\"a\";"), parsed("print -\"a\";"));
+    assert_eq!(optimized("print 1 < \"a\";"), parsed("print 1 < \"a\";"));
+}
+
+#[test]
+fn deadCode()
+{
+    assert_eq!(optimized("if (false) print 1; while (nil) print 2;"), "");
+    assert_eq!(optimized("if (1 > 2) print 1; else print 2;"), optimized("print 2;"));
+    assert_eq!(optimized("while (x) if (false) print 1;"), optimized("while (x) {}"));
+    //The initialiser still runs
+    assert_eq!(optimized("for (var i = f(); false;) print i;"), optimized("{ var i = f(); }"));
+}